@@ -0,0 +1,99 @@
+use std::{env, path::PathBuf, process};
+
+use bevy::{log::LogPlugin, prelude::*};
+use idle_inspection::game::{
+    resources::{
+        facility_map::FacilityMap,
+        scenario::{Scenario, SimRun},
+        world_seed::WorldSeed,
+    },
+    simulation::{HeadlessPlugin, SimulationPlugin},
+};
+
+const USAGE: &str = "usage: sim <scenario.json> [--timestep <seconds>] [--seed <seed>] \
+                     [--duration <seconds>] [--output <metrics.json>]";
+
+struct Args {
+    scenario: PathBuf,
+    timestep: f32,
+    seed: Option<u64>,
+    duration: f32,
+    output: Option<PathBuf>,
+}
+
+impl Args {
+    fn parse() -> Result<Self, String> {
+        let mut args = env::args().skip(1);
+        let mut scenario = None;
+        let mut parsed = Args {
+            scenario: PathBuf::new(),
+            timestep: 1.0 / 60.0,
+            seed: None,
+            duration: 60.0,
+            output: None,
+        };
+        while let Some(arg) = args.next() {
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (arg.clone(), None),
+            };
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("missing value for {}", name))
+            };
+            match name.as_str() {
+                "--timestep" => parsed.timestep = parse(&name, value()?)?,
+                "--seed" => parsed.seed = Some(parse(&name, value()?)?),
+                "--duration" => parsed.duration = parse(&name, value()?)?,
+                "--output" => parsed.output = Some(PathBuf::from(value()?)),
+                _ if name.starts_with("--") => return Err(format!("unknown option {}", name)),
+                _ => scenario = Some(PathBuf::from(arg)),
+            }
+        }
+        if parsed.timestep <= 0.0 {
+            return Err("--timestep must be positive".to_string());
+        }
+        parsed.scenario = scenario.ok_or("missing scenario file")?;
+        Ok(parsed)
+    }
+}
+
+fn parse<T: std::str::FromStr>(name: &str, value: String) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: {}", name, value))
+}
+
+fn main() {
+    let args = Args::parse().unwrap_or_else(|error| {
+        eprintln!("{}\n{}", error, USAGE);
+        process::exit(2);
+    });
+    let scenario = Scenario::load(&args.scenario).unwrap_or_else(|error| {
+        eprintln!("can not load {}: {}", args.scenario.display(), error);
+        process::exit(1);
+    });
+    let facility_map = match &scenario.map {
+        Some(path) => FacilityMap::load(path).unwrap_or_else(|error| {
+            eprintln!("can not load map {}: {}", path.display(), error);
+            process::exit(1);
+        }),
+        None => FacilityMap::empty(),
+    };
+    let seed = args.seed.or(scenario.seed).unwrap_or_default();
+    let mut sim_run = SimRun::new(args.timestep, args.duration);
+    sim_run.output = args.output;
+
+    App::build()
+        .insert_resource(WorldSeed::new(seed))
+        .insert_resource(facility_map)
+        .insert_resource(scenario)
+        .insert_resource(sim_run)
+        .add_plugins(MinimalPlugins)
+        .add_plugin(LogPlugin)
+        .add_plugin(HeadlessPlugin)
+        .add_plugin(SimulationPlugin)
+        .run();
+}
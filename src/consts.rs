@@ -1 +1,2 @@
 pub const PHYSICS_SCALE: f32 = 20.0;
+pub const AMBIENT_TEMPERATURE: f32 = 20.0;
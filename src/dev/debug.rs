@@ -1,5 +1,8 @@
 use bevy::{math::Vec3, prelude::*};
 use bevy_prototype_debug_lines::DebugLines;
+use bevy_rapier2d::prelude::*;
+
+use crate::game::components::robot::*;
 
 pub fn relative_line(lines: &mut DebugLines, transform: &Transform, end: Vec2) {
     lines.line_colored(
@@ -13,3 +16,19 @@ pub fn relative_line(lines: &mut DebugLines, transform: &Transform, end: Vec2) {
         Color::RED,
     );
 }
+
+/// Velocity and heading of everything with motors.
+pub fn draw_velocities(
+    mut lines: ResMut<DebugLines>,
+    drivables: Query<(&Transform, &RigidBodyVelocity, &RigidBodyPosition), With<Motors>>,
+) {
+    drivables.for_each(|(transform, rb_vel, rb_pos)| {
+        let dir = rb_pos.position.transform_vector(&Vector::y());
+        relative_line(
+            &mut lines,
+            transform,
+            Vec2::new(rb_vel.linvel.x, rb_vel.linvel.y),
+        );
+        relative_line(&mut lines, transform, Vec2::new(dir.x, dir.y));
+    });
+}
@@ -2,7 +2,7 @@ use bevy::prelude::*;
 use bevy_inspector_egui::*;
 
 use crate::game::{
-    components::robot::*,
+    components::{exploration::*, robot::*},
     resources::{sprite_asset::SpriteAsset, terrain_collider::TerrainCollider}, types::ItemType,
 };
 
@@ -34,6 +34,32 @@ impl Plugin for InspectAllPlugin {
         registry.register::<JointType>();
         registry.register::<Battery>();
         registry.register::<ItemOrigin>();
+        registry.register::<ItemSeed>();
+        registry.register::<Powered>();
+        registry.register::<RangeSensor>();
+        registry.register::<Autopilot>();
+        registry.register::<WheelOdometry>();
+        registry.register::<PoseEstimate>();
+        registry.register::<PoseGhost>();
+        registry.register::<FogTile>();
+        registry.register::<Manometer>();
+        registry.register::<PressureSource>();
+        registry.register::<InspectionProgress>();
+        registry.register::<Fault>();
+        registry.register::<NeedsRepair>();
+        registry.register::<GasSensor>();
+        registry.register::<GasLeak>();
+        registry.register::<PipeSource>();
+        registry.register::<Valve>();
+        registry.register::<ChargingStation>();
+        registry.register::<Door>();
+        registry.register::<ItemMass>();
+        registry.register::<RobotMass>();
+        registry.register::<Traction>();
+        registry.register::<GasTile>();
+        registry.register::<HotSpot>();
+        registry.register::<InspectionQuality>();
+        registry.register::<Undiscovered>();
     }
 }
 
@@ -1,2 +1,3 @@
 pub mod inspector;
-pub mod debug;
\ No newline at end of file
+pub mod debug;
+pub mod plugin;
@@ -0,0 +1,20 @@
+use bevy::prelude::*;
+
+use crate::{
+    dev::{debug, inspector::InspectAllPlugin},
+    game::schedule::GameSystem,
+};
+
+/// The world inspector and velocity lines, only built with the `dev` feature. Needs the
+/// `UiPlugin`.
+pub struct DevPlugin;
+
+impl Plugin for DevPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_plugin(InspectAllPlugin).add_system_set(
+            GameSystem::Ui
+                .set()
+                .with_system(debug::draw_velocities.system()),
+        );
+    }
+}
@@ -1,12 +1,16 @@
+use std::cell::RefCell;
+
 use crate::game::types::*;
 use bevy::{log, prelude::*};
 use bevy_interact_2d::{Group, Interactable};
+use bevy_rapier2d::prelude::RigidBodyType;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 use crate::game::{
     bundles::{
         interaction_marker::InteractionMarkerBundle, item::ItemBundle, physics::PhysicsBundle,
     },
-    components::robot::*,
+    components::{exploration::Undiscovered, robot::*},
     resources::{item_collection::*, item_information::*},
 };
 
@@ -15,6 +19,7 @@ pub struct SpawnItem {
     children: Vec<SpawnItem>,
     handle: Handle<LoadedItem>,
     ap: Option<AttachmentPointId>,
+    seed: Option<u64>,
 }
 
 impl SpawnItem {
@@ -23,6 +28,7 @@ impl SpawnItem {
             children: vec![],
             handle,
             ap: None,
+            seed: None,
         }
     }
 
@@ -31,6 +37,7 @@ impl SpawnItem {
             children: vec![],
             handle,
             ap: Some(ap),
+            seed: None,
         }
     }
 
@@ -43,6 +50,8 @@ pub struct ItemSpawner<'w> {
     pub items: &'w Assets<LoadedItem>,
     pub information_collection: &'w InformationCollection,
     pub item_collection: &'w ItemCollection,
+    /// Seeds the items built by this spawner, unless they are given one.
+    seeds: RefCell<StdRng>,
 }
 
 impl<'w> ItemSpawner<'w> {
@@ -50,14 +59,20 @@ impl<'w> ItemSpawner<'w> {
         items: &'w Assets<LoadedItem>,
         information_collection: &'w InformationCollection,
         item_collection: &'w ItemCollection,
+        seed: u64,
     ) -> Self {
         Self {
             items,
             information_collection,
             item_collection,
+            seeds: RefCell::new(StdRng::seed_from_u64(seed)),
         }
     }
 
+    fn next_seed(&self) -> Option<u64> {
+        Some(self.seeds.borrow_mut().gen())
+    }
+
     pub fn item(&self, handle: &Handle<LoadedItem>) -> ItemBuilder {
         ItemBuilder {
             items: self.items,
@@ -67,6 +82,7 @@ impl<'w> ItemSpawner<'w> {
             attach_to: None,
             spawn_item: Some(SpawnItem::root(handle.clone())),
             transform: Transform::default(),
+            seed: self.next_seed(),
         }
     }
 
@@ -84,6 +100,7 @@ impl<'w> ItemSpawner<'w> {
             attach_to: Some((parent, aid)),
             spawn_item: Some(SpawnItem::root(handle.clone())),
             transform: Transform::default(),
+            seed: self.next_seed(),
         }
     }
 }
@@ -96,6 +113,7 @@ pub struct ItemBuilder<'w> {
     attach_to: Option<(Entity, AttachmentPointId)>,
     spawn_item: Option<SpawnItem>,
     transform: Transform,
+    seed: Option<u64>,
 }
 
 impl<'w> ItemBuilder<'w> {
@@ -103,11 +121,17 @@ impl<'w> ItemBuilder<'w> {
         self.attach_to = Some((parent, aid));
         self
     }
+    /// Places a root item, attached items follow their parent.
     pub fn transform(&mut self, transform: Transform) -> &mut Self {
-        // TODO use transform
         self.transform = transform;
         self
     }
+    /// Seeds the randomized components of the item and its attachments, the next seed of the
+    /// spawner is used otherwise.
+    pub fn seed(&mut self, seed: u64) -> &mut Self {
+        self.seed = Some(seed);
+        self
+    }
     pub fn attach(&mut self, handle: &Handle<LoadedItem>, id: AttachmentPointId) -> &mut Self {
         if let Some(spawn_item) = &mut self.spawn_item {
             spawn_item.add_child(SpawnItem::child(handle.clone(), id));
@@ -129,10 +153,12 @@ impl<'w> ItemBuilder<'w> {
             attach_to: None,
             spawn_item: Some(SpawnItem::child(handle.clone(), id)),
             transform: Transform::default(),
+            seed: None,
         };
         let spawner = f(child_spawner);
         if let Some(spawn_item) = &mut self.spawn_item {
-            if let Some(child_item) = spawner.spawn_item.clone() {
+            if let Some(mut child_item) = spawner.spawn_item.clone() {
+                child_item.seed = spawner.seed;
                 spawn_item.add_child(child_item);
             } else {
                 log::warn!(
@@ -149,11 +175,15 @@ impl<'w> ItemBuilder<'w> {
         if let Some(spawn_item) = &self.spawn_item {
             if let Some(item) = self.items.get(spawn_item.handle.clone()) {
                 if let Some(information) = self.information_collection.get(&spawn_item.handle) {
+                    // only the builders of attach_then() lack one, their parent seeds them
+                    let seed = self.seed.unwrap_or_default();
+                    let mut rng = StdRng::seed_from_u64(seed);
                     let bundle = ItemBundle::new(item, &information);
                     let markers = self.interaction_markers(&bundle.attachments);
                     let parent = commands
                         .spawn_bundle(bundle)
                         .insert(ParentEntity::WaitForAttach)
+                        .insert(ItemSeed(seed))
                         .with_children(|cb| {
                             markers.into_iter().for_each(|im| {
                                 cb.spawn_bundle(im);
@@ -165,10 +195,15 @@ impl<'w> ItemBuilder<'w> {
                             .entity(parent)
                             .insert(WantToAttach::to(*super_parent, *aid));
                     } else {
-                        commands.entity(parent).insert(WantToAttach::me());
+                        commands
+                            .entity(parent)
+                            .insert(self.transform)
+                            .insert(WantToAttach::me());
                     }
-                    Self::attach_additional_components(commands, item.item_type, parent);
+                    Self::attach_additional_components(commands, item.item_type, parent, &mut rng);
                     spawn_item.children.iter().for_each(|child| {
+                        // draw a seed either way, so the other children keep theirs
+                        let seed = rng.gen();
                         let child_spawner = &mut Self {
                             items: self.items,
                             information_collection: self.information_collection,
@@ -176,6 +211,7 @@ impl<'w> ItemBuilder<'w> {
                             attach_to: Some((parent, child.ap.unwrap())),
                             spawn_item: Some(child.clone()),
                             transform: Transform::default(),
+                            seed: Some(child.seed.unwrap_or(seed)),
                         };
                         child_spawner.build(commands);
                     });
@@ -200,28 +236,51 @@ impl<'w> ItemBuilder<'w> {
         }
     }
 
-    fn attach_additional_components(commands: &mut Commands, item_type: ItemType, parent: Entity) {
+    fn attach_additional_components(
+        commands: &mut Commands,
+        item_type: ItemType,
+        parent: Entity,
+        rng: &mut StdRng,
+    ) {
         match item_type {
-            ItemType::Robot(RobotItemType::Body) => {}
+            ItemType::Robot(RobotItemType::Body { max_payload }) => {
+                commands
+                    .entity(parent)
+                    .insert(Autopilot::default())
+                    .insert(PoseEstimate::default())
+                    .insert(RobotMass::new(max_payload));
+            }
             ItemType::Robot(RobotItemType::GroundPropulsion) => {
-                commands.entity(parent).insert(Motors {
-                    angular_damping: 0.5,
-                    linear_damping: 0.5,
-                    linear_speed: 4000.0,
-                    angular_speed: 2000.0,
-                });
+                commands
+                    .entity(parent)
+                    .insert(Motors {
+                        angular_damping: 0.5,
+                        linear_damping: 0.5,
+                        linear_speed: 4000.0,
+                        angular_speed: 2000.0,
+                    })
+                    .insert(WheelOdometry::default())
+                    .insert(Traction::default());
             }
-            ItemType::Robot(RobotItemType::Camera) => {
+            ItemType::Robot(RobotItemType::Camera {
+                width,
+                height,
+                noise,
+                spectrum,
+            }) => {
                 commands.entity(parent).insert(ImageQuality {
-                    width: 1.0,
-                    height: 1.0,
-                    noise: 0.0,
+                    width,
+                    height,
+                    noise,
+                    spectrum,
                 });
             }
             ItemType::Robot(RobotItemType::CameraLens(CameraLensType::Wide { focal_length })) => {
                 commands
                     .entity(parent)
-                    .insert(CameraLens::new_wide(focal_length));
+                    .insert(CameraLens::new_wide(focal_length))
+                    .insert(LensTargets::default())
+                    .insert(InspectionQuality::default());
             }
             ItemType::Robot(RobotItemType::CameraLens(CameraLensType::Telephoto {
                 focal_lengths,
@@ -229,7 +288,9 @@ impl<'w> ItemBuilder<'w> {
             })) => {
                 commands
                     .entity(parent)
-                    .insert(CameraLens::new_telephoto(focal_lengths, focus_speed));
+                    .insert(CameraLens::new_telephoto(focal_lengths, focus_speed))
+                    .insert(LensTargets::default())
+                    .insert(InspectionQuality::default());
             }
             ItemType::Robot(RobotItemType::Battery {
                 capacity,
@@ -242,11 +303,96 @@ impl<'w> ItemBuilder<'w> {
                     charge_speed,
                 });
             }
-            ItemType::Manometer(ManometerItemType::Icon { progress }) => {
-                commands.entity(parent).insert(Manometer {
-                    progress,
-                    inspections: 0.,
-                });
+            ItemType::Robot(RobotItemType::RangeSensor {
+                rays,
+                field_of_view,
+                max_range,
+                noise,
+                power_consumption,
+            }) => {
+                commands
+                    .entity(parent)
+                    .insert(RangeSensor::new(
+                        rays,
+                        field_of_view,
+                        max_range,
+                        noise,
+                        power_consumption,
+                    ))
+                    .insert(Powered::default());
+            }
+            ItemType::Robot(RobotItemType::GasSensor {
+                sensitivity,
+                response_time,
+                power_consumption,
+            }) => {
+                commands
+                    .entity(parent)
+                    .insert(GasSensor::new(
+                        sensitivity,
+                        response_time,
+                        power_consumption,
+                    ))
+                    .insert(Powered::default());
+            }
+            ItemType::Manometer(ManometerItemType::Icon {
+                progress,
+                range,
+                allowed,
+                unit,
+                interval,
+            }) => {
+                let band = allowed.1 - allowed.0;
+                commands
+                    .entity(parent)
+                    .insert(Manometer::new(progress, range, allowed, unit, interval))
+                    .insert(InspectionProgress::default())
+                    .insert(PressureSource {
+                        base: allowed.0 + band * rng.gen_range(0.2..0.8),
+                        amplitude: band * 0.1,
+                        period: rng.gen_range(20.0..60.0),
+                        phase: rng.gen_range(0.0..std::f32::consts::TAU),
+                        noise: band * 0.01,
+                    })
+                    .insert(Undiscovered);
+            }
+            ItemType::Environment(EnvironmentItemType::HotSpot { max_temperature }) => {
+                let max_temperature = if max_temperature > 0.0 {
+                    max_temperature
+                } else {
+                    80.0
+                };
+                commands
+                    .entity(parent)
+                    .insert(HotSpot::new(
+                        max_temperature * rng.gen_range(0.6..1.25),
+                        max_temperature,
+                    ))
+                    .insert(Undiscovered);
+            }
+            ItemType::Environment(EnvironmentItemType::Wall) => {
+                commands.entity(parent).insert(RigidBodyType::Static);
+            }
+            ItemType::Environment(EnvironmentItemType::ChargingStation { range }) => {
+                commands
+                    .entity(parent)
+                    .insert(ChargingStation { range })
+                    .insert(RigidBodyType::Static);
+            }
+            ItemType::Environment(EnvironmentItemType::Door { reach }) => {
+                commands
+                    .entity(parent)
+                    .insert(Door::new(reach, AccessCondition::None))
+                    .insert(RigidBodyType::Static);
+            }
+            ItemType::Environment(EnvironmentItemType::Gate { reach, access }) => {
+                commands
+                    .entity(parent)
+                    .insert(Door::new(reach, access))
+                    .insert(RigidBodyType::Static);
+            }
+            ItemType::Environment(EnvironmentItemType::Pipe) | ItemType::Manometer(..) => {
+                commands.entity(parent).insert(Undiscovered);
             }
             ItemType::Marker(MarkerItemType::Waypoint) => {
                 commands.entity(parent).insert(WaypointMarker);
@@ -28,6 +28,7 @@ pub struct ItemBundle {
     pub attachments: AttachmentMap<Attachment>,
     pub item_name: ItemName,
     pub origin: ItemOrigin,
+    pub mass: ItemMass,
     #[bundle]
     pub animation_bundle: AnimationBundle,
     #[bundle]
@@ -60,6 +61,7 @@ impl ItemBundle {
             sprite_asset: item.sprite.clone(),
             animation_bundle: AnimationBundle::new(0.3),
             origin: ItemOrigin::new(item.origin),
+            mass: ItemMass(item.mass()),
             attachments: AttachmentMap(
                 item.attachment_points
                     .0
@@ -93,16 +95,14 @@ impl ItemBundle {
             collider: ColliderBundle {
                 flags: ColliderFlags {
                     active_hooks: ActiveHooks::FILTER_CONTACT_PAIRS,
-                    active_events: ActiveEvents::INTERSECTION_EVENTS,
+                    active_events: ActiveEvents::INTERSECTION_EVENTS | ActiveEvents::CONTACT_EVENTS,
                     ..Default::default()
                 },
                 shape: ColliderShape::cuboid(
                     item.sprite.size.0 / (2. * PHYSICS_SCALE),
                     item.sprite.size.1 / (2. * PHYSICS_SCALE),
                 ),
-                mass_properties: ColliderMassProps::Density(
-                    item.sprite.size.0 * item.sprite.size.1 / (20. * PHYSICS_SCALE),
-                ),
+                mass_properties: ColliderMassProps::Density(item.mass() / item.collider_area()),
                 collider_type: ColliderType::Sensor,
                 ..Default::default()
             },
@@ -0,0 +1,18 @@
+#[cfg(feature = "dev")]
+use bevy_inspector_egui::Inspectable;
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct FogTile {
+    pub index: usize,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct Undiscovered;
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct GasTile {
+    pub index: usize,
+}
@@ -1,4 +1,5 @@
 pub mod robot;
 pub mod animation;
 pub mod collision_filter;
-pub mod ui;
\ No newline at end of file
+pub mod ui;
+pub mod exploration;
\ No newline at end of file
@@ -1,11 +1,13 @@
 use std::{fmt::Display, ops::Range};
 
 use bevy::{log, prelude::*, utils::HashMap};
+#[cfg(feature = "dev")]
 use bevy_inspector_egui::Inspectable;
 
-use crate::game::types::ItemType;
+use crate::game::types::{AccessCondition, ItemType, PressureUnit, Spectrum, SurfaceType};
 
-#[derive(Debug, Inspectable, Default)]
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct Motors {
     pub linear_speed: f32,
     pub angular_speed: f32,
@@ -13,7 +15,70 @@ pub struct Motors {
     pub angular_damping: f32,
 }
 
-#[derive(Debug, Inspectable, Default)]
+/// Surface under a propulsion item, scales its force, damping and power use.
+#[derive(Debug)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct Traction {
+    pub surface: SurfaceType,
+    pub friction: f32,
+    pub rolling_resistance: f32,
+    pub power_cost: f32,
+}
+
+impl Traction {
+    pub fn new(surface: SurfaceType) -> Self {
+        let properties = surface.properties();
+        Self {
+            surface,
+            friction: properties.friction,
+            rolling_resistance: properties.rolling_resistance,
+            power_cost: properties.power_cost,
+        }
+    }
+}
+
+impl Default for Traction {
+    fn default() -> Self {
+        Self::new(SurfaceType::default())
+    }
+}
+
+/// Mass of a robot summed over its attached items, the center of mass is relative to
+/// the body.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct RobotMass {
+    pub total: f32,
+    pub center_of_mass: Vec2,
+    /// Everything carried on top of the body itself.
+    pub payload: f32,
+    pub max_payload: f32,
+}
+
+impl RobotMass {
+    pub fn new(max_payload: f32) -> Self {
+        Self {
+            max_payload,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_overloaded(&self) -> bool {
+        self.max_payload > 0.0 && self.payload > self.max_payload
+    }
+
+    /// Share of the motor force left for moving, an overloaded robot crawls.
+    pub fn motor_efficiency(&self) -> f32 {
+        if self.is_overloaded() {
+            self.max_payload / self.payload
+        } else {
+            1.0
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct CameraLens {
     pub focal_length_range: Range<f32>,
     pub focus_speed: f32,
@@ -47,21 +112,213 @@ impl CameraLens {
     }
 }
 
-#[derive(Debug, Inspectable, Default)]
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct ImageQuality {
     pub width: f32,
     pub height: f32,
     pub noise: f32,
+    pub spectrum: Spectrum,
+}
+
+#[derive(Debug, Default)]
+pub struct LensTargets(pub Vec<Entity>);
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct InspectionQuality {
+    pub target: Option<Entity>,
+    pub quality: f32,
+    pub focus: f32,
+    pub detail: f32,
+    pub blur: f32,
+    pub angle: f32,
+    pub blocked: usize,
 }
 
-#[derive(Debug, Inspectable, Default)]
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct Battery {
     pub capacity: f32,
     pub charge_speed: f32,
     pub charge: f32,
 }
 
-#[derive(Debug, Inspectable, Default)]
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct Powered(pub bool);
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct RangeSensor {
+    pub rays: usize,
+    pub field_of_view: f32,
+    pub max_range: f32,
+    pub noise: f32,
+    pub power_consumption: f32,
+    pub readings: Vec<f32>,
+}
+
+impl RangeSensor {
+    pub fn new(
+        rays: usize,
+        field_of_view: f32,
+        max_range: f32,
+        noise: f32,
+        power_consumption: f32,
+    ) -> Self {
+        Self {
+            rays,
+            field_of_view,
+            max_range,
+            noise,
+            power_consumption,
+            readings: vec![],
+        }
+    }
+
+    /// Angle of the ray in radians relative to the sensors forward (y) axis.
+    pub fn ray_angle(&self, ray: usize) -> f32 {
+        if self.rays <= 1 {
+            return 0.0;
+        }
+        let fov = self.field_of_view.to_radians();
+        // a full circle would cast the first and last ray in the same direction
+        let spread = if self.field_of_view >= 360.0 {
+            fov * (self.rays - 1) as f32 / self.rays as f32
+        } else {
+            fov
+        };
+        -spread / 2.0 + spread * ray as f32 / (self.rays - 1) as f32
+    }
+
+    /// Closest obstacle as (ray angle, distance), if any ray hit something.
+    pub fn nearest(&self) -> Option<(f32, f32)> {
+        self.readings
+            .iter()
+            .enumerate()
+            .filter(|(_, distance)| **distance < self.max_range)
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(i, distance)| (self.ray_angle(i), *distance))
+    }
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct GasSensor {
+    pub sensitivity: f32,
+    pub response_time: f32,
+    pub power_consumption: f32,
+    pub reading: f32,
+}
+
+impl GasSensor {
+    pub fn new(sensitivity: f32, response_time: f32, power_consumption: f32) -> Self {
+        Self {
+            sensitivity,
+            response_time,
+            power_consumption,
+            reading: 0.0,
+        }
+    }
+
+    /// Moves the reading towards the concentration like a first order low pass.
+    pub fn respond(&mut self, concentration: f32, dt: f32) {
+        let target = concentration * self.sensitivity;
+        let alpha = if self.response_time > 0.0 {
+            (dt / self.response_time).min(1.0)
+        } else {
+            1.0
+        };
+        self.reading += (target - self.reading) * alpha;
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct Autopilot {
+    pub enabled: bool,
+    pub arrive_distance: f32,
+    pub avoid_distance: f32,
+    /// Plans a route around expensive surfaces instead of driving straight.
+    pub prefer_cheap_surfaces: bool,
+    pub route: Vec<Vec2>,
+    /// The waypoint the route was planned for, so an unreachable one is not searched every frame.
+    pub planned_for: Option<Vec2>,
+}
+
+impl Default for Autopilot {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            arrive_distance: 20.0,
+            avoid_distance: 40.0,
+            prefer_cheap_surfaces: false,
+            route: vec![],
+            planned_for: None,
+        }
+    }
+}
+
+/// Wheel encoder of a track. It follows the motor commands of the robot rather than its
+/// movement, so it keeps counting while the robot pushes against a wall.
+#[derive(Debug)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct WheelOdometry {
+    pub slip: f32,
+    /// Speed the encoder reports, in pixels per second.
+    pub speed: f32,
+    /// Forward force and torque commanded to the track this frame.
+    pub force: f32,
+    pub torque: f32,
+    /// Speed and turn rate of the robot the commands add up to, in physics units.
+    pub linear: f32,
+    pub angular: f32,
+}
+
+impl Default for WheelOdometry {
+    fn default() -> Self {
+        Self {
+            slip: 0.05,
+            speed: 0.0,
+            force: 0.0,
+            torque: 0.0,
+            linear: 0.0,
+            angular: 0.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct PoseEstimate {
+    pub position: Vec2,
+    pub rotation: f32,
+    pub drift: f32,
+    pub use_estimate: bool,
+    pub initialized: bool,
+}
+
+impl Default for PoseEstimate {
+    fn default() -> Self {
+        Self {
+            position: Vec2::ZERO,
+            rotation: 0.0,
+            drift: 0.01,
+            use_estimate: false,
+            initialized: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct PoseGhost {
+    pub robot: Entity,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct AttachmentPointMarker {
     // pub attached: Option<Entity>,
     pub id: AttachmentPointId,
@@ -78,7 +335,8 @@ impl AttachmentPointMarker {
     }
 }
 
-#[derive(serde::Deserialize, Debug, Clone, Copy, Inspectable, Default)]
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct ItemSize(pub usize);
 
 impl ItemSize {
@@ -87,7 +345,8 @@ impl ItemSize {
     }
 }
 
-#[derive(Clone, Inspectable, Default)]
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct Attachment {
     pub id: AttachmentPointId,
     pub max_size: ItemSize,
@@ -121,7 +380,8 @@ impl Attachment {
     }
 }
 
-#[derive(Debug, Inspectable)]
+#[derive(Debug)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub enum WantToAttach {
     Me,
     To {
@@ -130,7 +390,8 @@ pub enum WantToAttach {
     },
 }
 
-#[derive(Debug, Inspectable)]
+#[derive(Debug)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct WantToAttachTo {
     pub parent: Entity,
     pub aid: AttachmentPointId,
@@ -148,25 +409,311 @@ impl WantToAttach {
     }
 }
 
-#[derive(Debug, Inspectable)]
+#[derive(Debug)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct ItemName(pub String);
 
-#[derive(serde::Deserialize, Debug, Clone, PartialEq, Inspectable, Copy)]
+#[derive(serde::Deserialize, Debug, Clone, PartialEq, Copy)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct WaypointMarker;
 
-#[derive(serde::Deserialize, Debug, Clone, PartialEq, Inspectable, Copy)]
+#[derive(serde::Deserialize, Debug, Clone, PartialEq, Copy)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct Manometer {
     pub inspections: f32,
     pub progress: f32,
+    pub pressure: f32,
+    pub range: (f32, f32),
+    pub allowed: (f32, f32),
+    pub unit: PressureUnit,
+    pub reading: Option<f32>,
+    pub quality: f32,
+    pub occluded: bool,
+    pub interval: f32,
+    pub last_inspected: Option<f64>,
+}
+
+impl Manometer {
+    pub const DEFAULT_INTERVAL: f32 = 120.0;
+
+    pub fn new(
+        progress: f32,
+        range: (f32, f32),
+        allowed: (f32, f32),
+        unit: PressureUnit,
+        interval: f32,
+    ) -> Self {
+        Self {
+            inspections: 0.,
+            progress,
+            pressure: (allowed.0 + allowed.1) / 2.0,
+            range,
+            allowed,
+            unit,
+            reading: None,
+            quality: 0.0,
+            occluded: false,
+            interval: if interval > 0.0 {
+                interval
+            } else {
+                Self::DEFAULT_INTERVAL
+            },
+            last_inspected: None,
+        }
+    }
+
+    pub fn status(&self, now: f64) -> InspectionStatus {
+        InspectionStatus::from_schedule(self.last_inspected, self.interval, now)
+    }
+
+    pub fn needs_inspection(&self, now: f64) -> bool {
+        self.status(now) != InspectionStatus::Ok
+    }
+
+    /// Position of the current pressure on the dial, from 0.0 (range start) to 1.0 (range end).
+    pub fn dial_fraction(&self) -> f32 {
+        if self.range.1 <= self.range.0 {
+            return 0.0;
+        }
+        ((self.pressure - self.range.0) / (self.range.1 - self.range.0)).clamp(0.0, 1.0)
+    }
+
+    pub fn in_band(&self, value: f32) -> bool {
+        value >= self.allowed.0 && value <= self.allowed.1
+    }
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct HotSpot {
+    pub temperature: f32,
+    pub max_temperature: f32,
+    pub progress: f32,
+    pub quality: f32,
+    pub reading: Option<f32>,
+    pub occluded: bool,
+    pub interval: f32,
+    pub last_inspected: Option<f64>,
+}
+
+impl HotSpot {
+    pub fn new(temperature: f32, max_temperature: f32) -> Self {
+        Self {
+            temperature,
+            max_temperature,
+            interval: Manometer::DEFAULT_INTERVAL,
+            ..Default::default()
+        }
+    }
+
+    pub fn status(&self, now: f64) -> InspectionStatus {
+        InspectionStatus::from_schedule(self.last_inspected, self.interval, now)
+    }
+
+    pub fn needs_inspection(&self, now: f64) -> bool {
+        self.status(now) != InspectionStatus::Ok
+    }
+
+    pub fn overheated(&self, value: f32) -> bool {
+        value > self.max_temperature
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InspectionStatus {
+    Uninspected,
+    Ok,
+    Due,
+    Overdue,
+}
+
+impl InspectionStatus {
+    /// Fraction of the interval a target may be due before it counts as overdue.
+    pub const GRACE: f32 = 0.5;
+
+    pub fn from_schedule(last_inspected: Option<f64>, interval: f32, now: f64) -> Self {
+        match last_inspected {
+            None => Self::Uninspected,
+            Some(last) => {
+                let elapsed = (now - last) as f32;
+                if elapsed < interval {
+                    Self::Ok
+                } else if elapsed < interval * (1.0 + Self::GRACE) {
+                    Self::Due
+                } else {
+                    Self::Overdue
+                }
+            }
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Self::Uninspected => Color::WHITE,
+            Self::Ok => Color::rgb(0.4, 1.0, 0.4),
+            Self::Due => Color::rgb(1.0, 0.85, 0.2),
+            Self::Overdue => Color::rgb(1.0, 0.3, 0.3),
+        }
+    }
+}
+
+impl Display for InspectionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Uninspected => write!(f, "Uninspected"),
+            Self::Ok => write!(f, "Ok"),
+            Self::Due => write!(f, "Due"),
+            Self::Overdue => write!(f, "Overdue"),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct InspectionProgress {
+    pub started: Option<f64>,
+    pub lens: Option<Entity>,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct PressureSource {
+    pub base: f32,
+    pub amplitude: f32,
+    pub period: f32,
+    pub phase: f32,
+    pub noise: f32,
+}
+
+impl PressureSource {
+    pub fn pressure_at(&self, seconds: f32) -> f32 {
+        if self.period <= 0.0 {
+            return self.base;
+        }
+        self.base
+            + self.amplitude * (std::f32::consts::TAU * seconds / self.period + self.phase).sin()
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub enum FaultKind {
+    Drift,
+    Spike,
+}
+
+impl Default for FaultKind {
+    fn default() -> Self {
+        Self::Drift
+    }
+}
+
+impl Display for FaultKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Drift => write!(f, "Drift"),
+            Self::Spike => write!(f, "Spike"),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct Fault {
+    pub kind: FaultKind,
+    pub offset: f32,
+    pub started: f64,
+    pub deadline: f64,
+}
+
+impl Fault {
+    /// Pressure offset the fault adds at the given time. Drifts ramp up over a third of the
+    /// time to the deadline, spikes apply immediately.
+    pub fn offset_at(&self, seconds: f64) -> f32 {
+        match self.kind {
+            FaultKind::Spike => self.offset,
+            FaultKind::Drift => {
+                let ramp = ((self.deadline - self.started) / 3.0).max(f64::EPSILON);
+                self.offset * ((seconds - self.started) / ramp).clamp(0.0, 1.0) as f32
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct NeedsRepair;
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct GasLeak {
+    pub rate: f32,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct ChargingStation {
+    pub range: f32,
+}
+
+/// A door, or a gate when it has an access condition. Robots open and close it with
+/// `RobotCommandType::OperateDoor` from within reach.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct Door {
+    pub open: bool,
+    pub reach: f32,
+    pub access: AccessCondition,
+    /// The last robot was turned away by the access condition.
+    pub denied: bool,
+}
+
+impl Door {
+    pub fn new(reach: f32, access: AccessCondition) -> Self {
+        Self {
+            open: false,
+            reach: if reach > 0.0 { reach } else { 40.0 },
+            access,
+            denied: false,
+        }
+    }
+
+    pub fn allows(&self, compliance: f32, inspections: usize) -> bool {
+        match self.access {
+            AccessCondition::None => true,
+            AccessCondition::Compliance { fraction } => compliance >= fraction,
+            AccessCondition::Inspections { count } => inspections >= count,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct PipeSource {
+    pub head: f32,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct Valve {
+    pub open: bool,
+}
+
+impl Default for Valve {
+    fn default() -> Self {
+        Self { open: true }
+    }
 }
 
 #[derive(serde::Deserialize, Debug, Clone, Default)]
-pub struct AttachmentMap<T: Inspectable + Clone>(pub HashMap<AttachmentPointId, T>);
+pub struct AttachmentMap<T: Clone>(pub HashMap<AttachmentPointId, T>);
 
-#[derive(Debug, Inspectable, Default)]
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct EmptyMarker;
 
-#[derive(serde::Deserialize, Debug, Clone, Inspectable, PartialEq, Eq, Copy)]
+#[derive(serde::Deserialize, Debug, Clone, PartialEq, Eq, Copy)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub enum JointType {
     Fixed,
     Ball,
@@ -180,13 +727,15 @@ impl Default for JointType {
     }
 }
 
-#[derive(Debug, Clone, Inspectable)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct SelectedAttachmentPoint {
     pub parent_item: Entity,
     pub attachment_point_id: AttachmentPointId,
 }
 
-#[derive(serde::Deserialize, Hash, Eq, PartialEq, Debug, Clone, Copy, Inspectable)]
+#[derive(serde::Deserialize, serde::Serialize, Hash, Eq, PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub enum AttachmentPointId {
     MainCamera,
     GroundPropulsionRight,
@@ -204,6 +753,8 @@ pub enum AttachmentPointId {
     ManometerPointer,
     ManometerMarkings,
 
+    HotSpot,
+
     Next,
     Previous,
 }
@@ -233,16 +784,20 @@ impl Display for AttachmentPointId {
             Self::ManometerPointer => write!(f, "Manometer Pointer"),
             Self::ManometerMarkings => write!(f, "Manometer Markings"),
 
+            Self::HotSpot => write!(f, "Hot Spot"),
+
             Self::Next => write!(f, "Next"),
             Self::Previous => write!(f, "Previous"),
         }
     }
 }
 
-#[derive(serde::Deserialize, Debug, Clone, Default, Inspectable)]
+#[derive(serde::Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct Selected(pub bool);
 
-#[derive(PartialEq, Eq, Clone, Inspectable, Debug, Copy)]
+#[derive(PartialEq, Eq, Clone, Debug, Copy)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub enum ParentEntity {
     WaitForAttach,
     None,
@@ -255,7 +810,19 @@ impl Default for ParentEntity {
     }
 }
 
-#[derive(serde::Deserialize, Debug, Clone, Default, Inspectable)]
+/// Seed the randomized components of an item were generated from, building the item again
+/// with it gives the same item.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct ItemSeed(pub u64);
+
+/// Mass of a single item in kilograms.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct ItemMass(pub f32);
+
+#[derive(serde::Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct ItemOrigin(pub f32, pub f32);
 
 impl ItemOrigin {
@@ -3,4 +3,7 @@ pub mod components;
 pub mod bundles;
 pub mod resources;
 pub mod builders;
-pub mod types;
\ No newline at end of file
+pub mod types;
+pub mod simulation;
+pub mod plugins;
+pub mod schedule;
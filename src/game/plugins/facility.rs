@@ -0,0 +1,55 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::{
+        resources::{
+            facility_map::FacilityMap, gas_field::GasField, surface_map::SurfaceMap,
+            terrain_collider::TerrainEditor, world_seed::WorldSeed,
+        },
+        schedule::GameSystem,
+        systems::{gas, structures, surface, terrain, tile_map},
+    },
+    GameState,
+};
+
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FacilitySystem {
+    /// Moves terrain edits into the colliders.
+    SyncTerrain,
+    /// Rebuilds the surface map, traction is updated after it.
+    UpdateSurfaceMap,
+    EmitLeaks,
+}
+
+/// The facility itself: map objects, terrain colliders, surfaces, doors and gas leaks.
+pub struct FacilityPlugin;
+
+impl Plugin for FacilityPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<WorldSeed>()
+            .init_resource::<FacilityMap>()
+            .init_resource::<TerrainEditor>()
+            .init_resource::<SurfaceMap>()
+            .init_resource::<GasField>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Game)
+                    .with_system(tile_map::spawn_objects.system())
+                    .with_system(terrain::spawn.system()),
+            )
+            .add_system_set(
+                GameSystem::Physics
+                    .set()
+                    .with_system(terrain::sync.system().label(FacilitySystem::SyncTerrain))
+                    .with_system(terrain::build.system().after(FacilitySystem::SyncTerrain))
+                    .with_system(
+                        surface::update_surface_map
+                            .system()
+                            .label(FacilitySystem::UpdateSurfaceMap)
+                            .after(FacilitySystem::SyncTerrain),
+                    )
+                    .with_system(structures::update_doors.system())
+                    .with_system(gas::emit_leaks.system().label(FacilitySystem::EmitLeaks))
+                    .with_system(gas::diffuse_gas.system().after(FacilitySystem::EmitLeaks)),
+            );
+    }
+}
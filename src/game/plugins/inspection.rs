@@ -0,0 +1,90 @@
+use bevy::{core::FixedTimestep, prelude::*};
+
+use crate::game::{
+    resources::{
+        compliance::FacilityCompliance, incident_log::*, inspection_log::InspectionLog,
+        pipe_line::PipeLine, pipe_network::PipeNetwork, ui::UiState,
+    },
+    schedule::{GameSystem, InspectionSystem},
+    systems::{alarms, inspection, pressure},
+};
+
+pub const TIMESTEP_1_PER_SECOND: f64 = 30.0 / 60.0;
+
+/// Pipes and manometers, their inspection by robots, compliance and alarms.
+pub struct InspectionPlugin;
+
+impl Plugin for InspectionPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        use InspectionSystem::*;
+        app.init_resource::<UiState>()
+            .init_resource::<PipeLine>()
+            .init_resource::<PipeNetwork>()
+            .init_resource::<InspectionLog>()
+            .init_resource::<FacilityCompliance>()
+            .init_resource::<IncidentLog>()
+            .init_resource::<FaultSchedule>()
+            .add_event::<AlarmEvent>()
+            .add_system_set(
+                GameSystem::Inspection
+                    .set()
+                    .with_system(alarms::inject_faults.system().label(Faults))
+                    .with_system(alarms::expire_faults.system().label(Faults))
+                    .with_system(pressure::update_pipe_network.system().label(Network))
+                    .with_system(
+                        pressure::simulate_pressure
+                            .system()
+                            .label(Pressure)
+                            .after(Network)
+                            .after(Faults),
+                    )
+                    .with_system(pressure::manometer_pointers.system().after(Pressure))
+                    .with_system(
+                        inspection::inspect_manometer
+                            .system()
+                            .label(Targets)
+                            .after(Pressure),
+                    )
+                    .with_system(
+                        inspection::inspection_quality
+                            .system()
+                            .label(Quality)
+                            .after(Targets),
+                    )
+                    .with_system(
+                        inspection::update_manometer_progress
+                            .system()
+                            .label(Progress)
+                            .after(Quality),
+                    )
+                    .with_system(
+                        inspection::update_hot_spot_progress
+                            .system()
+                            .label(Progress)
+                            .after(Quality),
+                    )
+                    .with_system(
+                        inspection::complete_manometer_progress
+                            .system()
+                            .label(Complete)
+                            .after(Progress),
+                    )
+                    .with_system(
+                        inspection::complete_hot_spot_progress
+                            .system()
+                            .label(Complete)
+                            .after(Progress),
+                    )
+                    .with_system(inspection::update_compliance.system().after(Complete))
+                    .with_system(alarms::handle_alarms.system().after(Complete)),
+            )
+            // a set has a single run criteria, so `build_pipe_line` checks the state itself
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedTimestep::step(TIMESTEP_1_PER_SECOND))
+                    .label(GameSystem::Inspection)
+                    .after(GameSystem::Sensing)
+                    .with_system(inspection::build_pipe_line.system()),
+            );
+    }
+}
@@ -0,0 +1,61 @@
+use bevy::prelude::*;
+use bevy_asset_loader::AssetLoader;
+use bevy_asset_ron::RonAssetPlugin;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    game::{
+        components::{collision_filter::RapierUserData, robot::ParentEntity},
+        resources::{item_collection::*, item_information::*},
+        schedule::GameSystem,
+        systems::{load, physics},
+    },
+    GameState,
+};
+
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ItemSystem {
+    /// Attaches waiting items and tags them with their robot.
+    SpawnJoints,
+}
+
+/// Loads the `.it` items and their sprites, and attaches items to each other with joints.
+pub struct ItemPlugin;
+
+impl Plugin for ItemPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        let hooks = RapierUserData {};
+        app.init_resource::<InformationCollection>()
+            .insert_resource(PhysicsHooksWithQueryObject(Box::new(hooks)))
+            .add_plugin(RapierPhysicsPlugin::<&ParentEntity>::default())
+            .add_plugin(RonAssetPlugin::<LoadedItem>::new(&["it"]));
+        AssetLoader::new(GameState::AssetLoading, GameState::SpriteLoading)
+            .with_collection::<ItemCollection>()
+            .build(app);
+        app.add_system_set(
+            SystemSet::on_enter(GameState::SpriteLoading)
+                .with_system(load::configure_physics.system())
+                .with_system(load::fill_information.system()),
+        )
+        .add_system_set(
+            GameSystem::Attach
+                .set()
+                .with_system(
+                    physics::spawn_joints
+                        .system()
+                        .label(ItemSystem::SpawnJoints),
+                )
+                .with_system(
+                    physics::set_collision_for_item_types
+                        .system()
+                        .after(ItemSystem::SpawnJoints),
+                )
+                .with_system(
+                    physics::update_robot_mass
+                        .system()
+                        .after(ItemSystem::SpawnJoints),
+                )
+                .with_system(physics::despawn_detached_items.system()),
+        );
+    }
+}
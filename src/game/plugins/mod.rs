@@ -0,0 +1,7 @@
+pub mod facility;
+pub mod inspection;
+pub mod item;
+pub mod power;
+pub mod robot;
+pub mod save;
+pub mod ui;
@@ -0,0 +1,33 @@
+use bevy::prelude::*;
+
+use crate::game::{
+    resources::robot_commands::RobotCommands, schedule::GameSystem, systems::robot_commands,
+};
+
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PowerSystem {
+    /// Batteries charge before the commands of the frame draw from them.
+    ChargeBatteries,
+}
+
+/// Executes the queued robot commands as far as the batteries allow, and recharges them.
+pub struct PowerPlugin;
+
+impl Plugin for PowerPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<RobotCommands>().add_system_set(
+            GameSystem::Execute
+                .set()
+                .with_system(
+                    robot_commands::charge_batteries
+                        .system()
+                        .label(PowerSystem::ChargeBatteries),
+                )
+                .with_system(
+                    robot_commands::handle_command
+                        .system()
+                        .after(PowerSystem::ChargeBatteries),
+                ),
+        );
+    }
+}
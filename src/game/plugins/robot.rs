@@ -0,0 +1,99 @@
+use bevy::prelude::*;
+
+use crate::game::{
+    plugins::facility::FacilitySystem,
+    resources::exploration_map::ExplorationMap,
+    schedule::{CommandSource, GameSystem},
+    systems::{exploration, gas, movement, odometry, physics, sensors, structures, surface},
+};
+
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RobotSystem {
+    /// Damping follows the traction of the same frame.
+    UpdateTraction,
+    /// The pose estimate integrates the odometry of the same frame.
+    WheelOdometry,
+}
+
+/// Driving, autopilot, sensing and localization of the robots.
+pub struct RobotPlugin;
+
+impl Plugin for RobotPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<ExplorationMap>()
+            .add_system_set(
+                GameSystem::Commands
+                    .set()
+                    .with_system(
+                        movement::plan_routes
+                            .system()
+                            .before(CommandSource::Autopilot),
+                    )
+                    .with_system(
+                        movement::set_initial_camera_lens
+                            .system()
+                            .label(CommandSource::Lens),
+                    )
+                    .with_system(
+                        movement::autopilot
+                            .system()
+                            .label(CommandSource::Autopilot)
+                            .after(CommandSource::Lens),
+                    )
+                    .with_system(
+                        structures::operate_doors
+                            .system()
+                            .label(CommandSource::Doors)
+                            .after(CommandSource::Autopilot),
+                    ),
+            )
+            .add_system_set(
+                GameSystem::Physics
+                    .set()
+                    .with_system(
+                        physics::adjust_damping
+                            .system()
+                            .after(RobotSystem::UpdateTraction),
+                    )
+                    .with_system(physics::reduce_sideways_vel.system())
+                    .with_system(
+                        surface::update_traction
+                            .system()
+                            .label(RobotSystem::UpdateTraction)
+                            .after(FacilitySystem::UpdateSurfaceMap),
+                    ),
+            )
+            .add_system_set(
+                GameSystem::Sensing
+                    .set()
+                    .with_system(
+                        sensors::range_sensors
+                            .system()
+                            .label(CommandSource::RangeSensors),
+                    )
+                    .with_system(
+                        gas::gas_sensors
+                            .system()
+                            .label(CommandSource::GasSensors)
+                            .after(CommandSource::RangeSensors),
+                    )
+                    .with_system(
+                        odometry::wheel_odometry
+                            .system()
+                            .label(RobotSystem::WheelOdometry),
+                    )
+                    .with_system(
+                        odometry::estimate_pose
+                            .system()
+                            .after(RobotSystem::WheelOdometry),
+                    )
+                    .with_system(exploration::reveal_by_cameras.system())
+                    .with_system(
+                        exploration::reveal_by_range_sensors
+                            .system()
+                            .after(CommandSource::RangeSensors),
+                    )
+                    .with_system(exploration::discover_items.system()),
+            );
+    }
+}
@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::{
+        plugins::item::ItemSystem,
+        resources::{offline::OfflineProgress, save_game::SaveGames},
+        schedule::GameSystem,
+        systems::{offline, save_game},
+    },
+    GameState,
+};
+
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SaveSystem {
+    /// Puts the state of a loaded save back into its items as they attach.
+    RestoreItems,
+}
+
+/// Save slots with autosave, and what the robots did while the game was closed. Loading
+/// replaces every item before the frame's phases run, saving happens at the end of the
+/// frame. Runs without a window, the save and offline panels are part of the `UiPlugin`.
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<SaveGames>()
+            .init_resource::<OfflineProgress>()
+            .add_system_to_stage(CoreStage::PreUpdate, save_game::load_game.system())
+            .add_system_to_stage(CoreStage::Last, save_game::save_game.system())
+            .add_system_set(
+                SystemSet::on_enter(GameState::Game).with_system(save_game::resume_latest.system()),
+            )
+            .add_system_set(
+                GameSystem::Attach
+                    .set()
+                    .with_system(
+                        save_game::restore_item_state
+                            .system()
+                            .label(SaveSystem::RestoreItems)
+                            .after(ItemSystem::SpawnJoints),
+                    )
+                    .with_system(
+                        offline::simulate_offline
+                            .system()
+                            .after(SaveSystem::RestoreItems),
+                    ),
+            );
+    }
+}
@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::TilemapPlugin;
+use bevy_egui::EguiPlugin;
+use bevy_interact_2d::InteractionPlugin;
+use bevy_prototype_debug_lines::DebugLinesPlugin;
+
+use crate::{
+    game::{
+        resources::{offline::OfflineProgress, save_game::SaveGames, ui::UiState},
+        schedule::{CommandSource, GameSystem},
+        systems::{
+            alarms, animations, camera, exploration, gas, interaction_marker, load, movement,
+            odometry, offline, save_game, sensors, structures, terrain, tile_map, ui,
+        },
+    },
+    GameState,
+};
+
+/// Everything that needs a window: the startup robot, camera, sprites, fog, heatmap,
+/// sensor rays, keyboard and mouse control, the terrain editor and the egui panels.
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<UiState>()
+            .init_resource::<SaveGames>()
+            .init_resource::<OfflineProgress>()
+            .add_plugin(EguiPlugin)
+            .add_plugin(TilemapPlugin)
+            .add_plugin(InteractionPlugin)
+            .add_plugin(DebugLinesPlugin)
+            .add_system_set(
+                SystemSet::on_enter(GameState::Game)
+                    .with_system(load::spawn_entities.system())
+                    .with_system(camera::setup.system())
+                    .with_system(tile_map::startup.system())
+                    .with_system(ui::load_assets.system())
+                    .with_system(ui::configure_visuals.system())
+                    .with_system(exploration::spawn_fog.system())
+                    .with_system(gas::spawn_heatmap.system())
+                    .with_system(load::set_texture_filters_to_nearest.system()),
+            )
+            .add_system_set(
+                GameSystem::Input
+                    .set()
+                    .with_system(camera::pan.system())
+                    .with_system(camera::zoom.system())
+                    .with_system(interaction_marker::select_marker.system())
+                    .with_system(
+                        movement::send_drive_robot
+                            .system()
+                            .label(CommandSource::Drive),
+                    )
+                    .with_system(
+                        movement::send_move_joint
+                            .system()
+                            .label(CommandSource::MoveJoint)
+                            .after(CommandSource::Drive),
+                    )
+                    .with_system(
+                        movement::zoom_cameras
+                            .system()
+                            .label(CommandSource::Zoom)
+                            .after(CommandSource::MoveJoint),
+                    )
+                    .with_system(movement::add_waypoint.system())
+                    .with_system(structures::place_structures.system())
+                    .with_system(terrain::update.system()),
+            )
+            .add_system_set(
+                GameSystem::Ui
+                    .set()
+                    .with_system(ui::update_ui_scale_factor.system())
+                    .with_system(ui::robot_config_ui.system())
+                    .with_system(ui::incident_log_ui.system())
+                    .with_system(ui::pipe_network_ui.system())
+                    .with_system(ui::terrain_editor_ui.system())
+                    .with_system(save_game::save_games_ui.system())
+                    .with_system(offline::offline_report_ui.system())
+                    .with_system(terrain::draw.system())
+                    .with_system(sensors::draw_sensor_rays.system())
+                    .with_system(interaction_marker::update_marker_color.system())
+                    .with_system(interaction_marker::show_marker.system())
+                    .with_system(animations::motors.system())
+                    .with_system(animations::cameras.system())
+                    .with_system(animations::sprite.system())
+                    .with_system(animations::battery.system())
+                    .with_system(animations::manometer.system())
+                    .with_system(animations::hot_spot.system())
+                    .with_system(odometry::spawn_pose_ghosts.system())
+                    .with_system(odometry::update_pose_ghosts.system())
+                    .with_system(exploration::update_fog.system())
+                    .with_system(gas::update_heatmap.system())
+                    .with_system(alarms::highlight_repairs.system()),
+            );
+    }
+}
@@ -0,0 +1,18 @@
+#[derive(Debug, Default, Clone)]
+pub struct FacilityCompliance {
+    pub targets: usize,
+    pub uninspected: usize,
+    pub due: usize,
+    pub overdue: usize,
+}
+
+impl FacilityCompliance {
+    /// Share of known inspection targets whose last inspection is still within its interval.
+    pub fn fraction(&self) -> f32 {
+        if self.targets == 0 {
+            return 1.0;
+        }
+        let compliant = self.targets - self.uninspected - self.due - self.overdue;
+        compliant as f32 / self.targets as f32
+    }
+}
@@ -0,0 +1,102 @@
+use bevy::prelude::*;
+
+pub struct ExplorationMap {
+    pub cell_size: f32,
+    pub origin: Vec2,
+    pub width: usize,
+    pub height: usize,
+    pub explored: Vec<bool>,
+}
+
+impl Default for ExplorationMap {
+    fn default() -> Self {
+        Self::new(64.0, Vec2::new(-1600.0, -1600.0), 50, 50)
+    }
+}
+
+impl ExplorationMap {
+    pub fn new(cell_size: f32, origin: Vec2, width: usize, height: usize) -> Self {
+        Self {
+            cell_size,
+            origin,
+            width,
+            height,
+            explored: vec![false; width * height],
+        }
+    }
+
+    pub fn cell(&self, position: Vec2) -> Option<usize> {
+        let local = (position - self.origin) / self.cell_size;
+        if local.x < 0.0 || local.y < 0.0 {
+            return None;
+        }
+        let (x, y) = (local.x as usize, local.y as usize);
+        if x >= self.width || y >= self.height {
+            None
+        } else {
+            Some(y * self.width + x)
+        }
+    }
+
+    pub fn cell_center(&self, index: usize) -> Vec2 {
+        let (x, y) = (index % self.width, index / self.width);
+        self.origin + (Vec2::new(x as f32, y as f32) + Vec2::splat(0.5)) * self.cell_size
+    }
+
+    // there is no fog outside of the map, so everything out there counts as explored
+    pub fn is_explored(&self, position: Vec2) -> bool {
+        self.cell(position)
+            .map(|index| self.explored[index])
+            .unwrap_or(true)
+    }
+
+    /// The cells covered by a rotated rectangle, possibly with duplicates.
+    pub fn rect_cells(&self, center: Vec2, half_extents: Vec2, rotation: f32) -> Vec<usize> {
+        let mut cells = Vec::new();
+        let radius = half_extents.length();
+        let (sin, cos) = rotation.sin_cos();
+        let mut y = center.y - radius;
+        while y <= center.y + radius {
+            let mut x = center.x - radius;
+            while x <= center.x + radius {
+                let offset = Vec2::new(x, y) - center;
+                let local = Vec2::new(
+                    cos * offset.x + sin * offset.y,
+                    -sin * offset.x + cos * offset.y,
+                );
+                if local.x.abs() <= half_extents.x && local.y.abs() <= half_extents.y {
+                    cells.extend(self.cell(Vec2::new(x, y)));
+                }
+                x += self.cell_size / 2.0;
+            }
+            y += self.cell_size / 2.0;
+        }
+        cells.extend(self.cell(center));
+        cells
+    }
+
+    /// The cells a line passes through, possibly with duplicates.
+    pub fn line_cells(&self, start: Vec2, end: Vec2) -> Vec<usize> {
+        let length = start.distance(end);
+        let steps = (length / (self.cell_size / 2.0)).ceil().max(1.0) as usize;
+        (0..=steps)
+            .filter_map(|step| self.cell(start.lerp(end, step as f32 / steps as f32)))
+            .collect()
+    }
+
+    pub fn any_unexplored(&self, cells: &[usize]) -> bool {
+        cells.iter().any(|index| !self.explored[*index])
+    }
+
+    pub fn reveal(&mut self, cells: &[usize]) {
+        cells.iter().for_each(|index| self.explored[*index] = true);
+    }
+
+    pub fn explored_fraction(&self) -> f32 {
+        if self.explored.is_empty() {
+            return 1.0;
+        }
+        self.explored.iter().filter(|explored| **explored).count() as f32
+            / self.explored.len() as f32
+    }
+}
@@ -0,0 +1,346 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::{log, prelude::*};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapObjectKind {
+    Pipe,
+    Manometer,
+    Wall,
+    Door,
+    Gate,
+    ChargingStation,
+    RobotStart,
+}
+
+impl MapObjectKind {
+    pub fn from_identifier(identifier: &str) -> Option<Self> {
+        match identifier {
+            "Pipe" => Some(Self::Pipe),
+            "Manometer" => Some(Self::Manometer),
+            "Wall" => Some(Self::Wall),
+            "Door" => Some(Self::Door),
+            "Gate" => Some(Self::Gate),
+            "ChargingStation" => Some(Self::ChargingStation),
+            "RobotStart" => Some(Self::RobotStart),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MapObject {
+    pub kind: MapObjectKind,
+    pub position: Vec2,
+    /// Counter-clockwise, in degrees.
+    pub rotation: f32,
+}
+
+impl MapObject {
+    pub fn transform(&self) -> Transform {
+        Transform {
+            translation: self.position.extend(0.0),
+            rotation: Quat::from_rotation_z(self.rotation.rem_euclid(360.0).to_radians()),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MapTile {
+    pub position: UVec2,
+    pub index: u16,
+}
+
+/// The image the tiles of a layer index into, row by row.
+#[derive(Debug, Clone)]
+pub struct MapTileset {
+    pub path: PathBuf,
+    /// In pixels.
+    pub size: Vec2,
+    pub tile_size: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct MapLayer {
+    pub name: String,
+    pub tileset: Option<MapTileset>,
+    pub tiles: Vec<MapTile>,
+}
+
+/// A hand-authored level, empty when the facility is generated.
+///
+/// The map is read from `--map path` or the `IDLE_INSPECTION_MAP` environment variable.
+#[derive(Debug, Clone)]
+pub struct FacilityMap {
+    pub path: Option<PathBuf>,
+    pub name: String,
+    /// Top left corner of the level in world pixels.
+    pub origin: Vec2,
+    pub grid_size: f32,
+    /// Size of the level in tiles.
+    pub size: UVec2,
+    pub layers: Vec<MapLayer>,
+    pub objects: Vec<MapObject>,
+}
+
+impl FacilityMap {
+    pub fn empty() -> Self {
+        Self {
+            path: None,
+            name: String::new(),
+            origin: Vec2::ZERO,
+            grid_size: 1.0,
+            size: UVec2::ZERO,
+            layers: vec![],
+            objects: vec![],
+        }
+    }
+
+    pub fn from_args() -> Option<PathBuf> {
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            if arg == "--map" {
+                return args.next().map(PathBuf::from);
+            } else if let Some(path) = arg.strip_prefix("--map=") {
+                return Some(PathBuf::from(path));
+            }
+        }
+        env::var("IDLE_INSPECTION_MAP").ok().map(PathBuf::from)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("ldtk") => ldtk::load(path),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown map format: {}", path.display()),
+            )),
+        }
+    }
+
+    /// Terrain is kept next to the map, or in the maps folder for generated facilities.
+    pub fn terrain_path(&self) -> PathBuf {
+        match &self.path {
+            Some(path) => path.with_extension("terrain.json"),
+            None => PathBuf::from("assets/maps/facility.terrain.json"),
+        }
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.path.is_some()
+    }
+
+    pub fn objects(&self, kind: MapObjectKind) -> impl Iterator<Item = &MapObject> {
+        self.objects
+            .iter()
+            .filter(move |object| object.kind == kind)
+    }
+
+    /// Converts from the map, which counts pixels down from the top left corner.
+    fn world_position(&self, px: [f32; 2]) -> Vec2 {
+        Vec2::new(self.origin.x + px[0], self.origin.y - px[1])
+    }
+}
+
+impl Default for FacilityMap {
+    fn default() -> Self {
+        match Self::from_args() {
+            Some(path) => match Self::load(&path) {
+                Ok(map) => {
+                    log::info!(
+                        "loaded map {} with {} objects",
+                        path.display(),
+                        map.objects.len()
+                    );
+                    map
+                }
+                Err(error) => {
+                    log::warn!("can not load map {}: {}", path.display(), error);
+                    Self::empty()
+                }
+            },
+            None => Self::empty(),
+        }
+    }
+}
+
+mod ldtk {
+    use super::*;
+
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Project {
+        defs: Definitions,
+        levels: Vec<Level>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Definitions {
+        #[serde(default)]
+        tilesets: Vec<Tileset>,
+    }
+
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Tileset {
+        uid: i64,
+        rel_path: Option<String>,
+        px_wid: f32,
+        px_hei: f32,
+        tile_grid_size: f32,
+    }
+
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Level {
+        identifier: String,
+        world_x: f32,
+        world_y: f32,
+        px_wid: f32,
+        px_hei: f32,
+        external_rel_path: Option<String>,
+        layer_instances: Option<Vec<Layer>>,
+    }
+
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Layer {
+        #[serde(rename = "__identifier")]
+        identifier: String,
+        #[serde(rename = "__gridSize")]
+        grid_size: f32,
+        #[serde(rename = "__tilesetDefUid")]
+        tileset_def_uid: Option<i64>,
+        #[serde(default)]
+        grid_tiles: Vec<Tile>,
+        #[serde(default)]
+        auto_layer_tiles: Vec<Tile>,
+        #[serde(default)]
+        entity_instances: Vec<EntityInstance>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Tile {
+        px: [f32; 2],
+        t: u16,
+    }
+
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct EntityInstance {
+        #[serde(rename = "__identifier")]
+        identifier: String,
+        px: [f32; 2],
+        #[serde(default)]
+        field_instances: Vec<Field>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Field {
+        #[serde(rename = "__identifier")]
+        identifier: String,
+        #[serde(rename = "__value")]
+        value: serde_json::Value,
+    }
+
+    fn read<T: serde::de::DeserializeOwned>(path: &Path) -> io::Result<T> {
+        serde_json::from_str(&fs::read_to_string(path)?).map_err(io::Error::from)
+    }
+
+    /// Loads the first level of the project, levels may be saved in separate files.
+    pub fn load(path: &Path) -> io::Result<FacilityMap> {
+        let project: Project = read(path)?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut level =
+            project.levels.into_iter().next().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "project has no levels")
+            })?;
+        if let Some(external) = &level.external_rel_path {
+            level = read(&dir.join(external))?;
+        }
+        let tilesets = &project.defs.tilesets;
+
+        let layers = level.layer_instances.unwrap_or_default();
+        let grid_size = layers
+            .iter()
+            .map(|layer| layer.grid_size)
+            .fold(0.0, f32::max)
+            .max(1.0);
+        let mut map = FacilityMap {
+            path: Some(path.to_path_buf()),
+            name: level.identifier,
+            origin: Vec2::new(level.world_x, -level.world_y),
+            grid_size,
+            size: UVec2::new(
+                (level.px_wid / grid_size).ceil() as u32,
+                (level.px_hei / grid_size).ceil() as u32,
+            ),
+            ..FacilityMap::empty()
+        };
+        // LDtk lists the top layer first
+        for layer in layers.into_iter().rev() {
+            let tiles = layer
+                .grid_tiles
+                .iter()
+                .chain(layer.auto_layer_tiles.iter())
+                .map(|tile| MapTile {
+                    position: UVec2::new(
+                        (tile.px[0] / layer.grid_size) as u32,
+                        // tile maps count rows from the bottom
+                        map.size
+                            .y
+                            .saturating_sub((tile.px[1] / layer.grid_size) as u32 + 1),
+                    ),
+                    index: tile.t,
+                })
+                .collect::<Vec<_>>();
+            if !tiles.is_empty() {
+                let tileset = layer
+                    .tileset_def_uid
+                    .and_then(|uid| tilesets.iter().find(|tileset| tileset.uid == uid))
+                    .and_then(|tileset| {
+                        // the asset server takes absolute paths as they are
+                        let path = dir.join(tileset.rel_path.as_ref()?);
+                        Some(MapTileset {
+                            path: fs::canonicalize(&path).unwrap_or(path),
+                            size: Vec2::new(tileset.px_wid, tileset.px_hei),
+                            tile_size: tileset.tile_grid_size,
+                        })
+                    });
+                if tileset.is_none() {
+                    log::warn!("map layer {} has no tileset", layer.identifier);
+                }
+                map.layers.push(MapLayer {
+                    name: layer.identifier.clone(),
+                    tileset,
+                    tiles,
+                });
+            }
+            for entity in &layer.entity_instances {
+                match MapObjectKind::from_identifier(&entity.identifier) {
+                    Some(kind) => {
+                        let rotation = entity
+                            .field_instances
+                            .iter()
+                            .find(|field| field.identifier == "Rotation")
+                            .and_then(|field| field.value.as_f64())
+                            .unwrap_or_default() as f32;
+                        let position = map.world_position(entity.px);
+                        map.objects.push(MapObject {
+                            kind,
+                            position,
+                            rotation,
+                        });
+                    }
+                    None => log::warn!("unknown map object: {}", entity.identifier),
+                }
+            }
+        }
+        Ok(map)
+    }
+}
@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+
+/// Gas concentration on a grid covering the same area as the exploration map.
+pub struct GasField {
+    pub cell_size: f32,
+    pub origin: Vec2,
+    pub width: usize,
+    pub height: usize,
+    pub diffusion: f32,
+    pub wind: Vec2,
+    pub decay: f32,
+    pub concentration: Vec<f32>,
+    pub sampled: Vec<bool>,
+}
+
+impl Default for GasField {
+    fn default() -> Self {
+        Self::new(64.0, Vec2::new(-1600.0, -1600.0), 50, 50)
+    }
+}
+
+impl GasField {
+    pub fn new(cell_size: f32, origin: Vec2, width: usize, height: usize) -> Self {
+        Self {
+            cell_size,
+            origin,
+            width,
+            height,
+            diffusion: 2000.0,
+            wind: Vec2::new(6.0, 2.0),
+            decay: 0.02,
+            concentration: vec![0.0; width * height],
+            sampled: vec![false; width * height],
+        }
+    }
+
+    pub fn cell(&self, position: Vec2) -> Option<usize> {
+        let local = (position - self.origin) / self.cell_size;
+        if local.x < 0.0 || local.y < 0.0 {
+            return None;
+        }
+        let (x, y) = (local.x as usize, local.y as usize);
+        if x >= self.width || y >= self.height {
+            None
+        } else {
+            Some(y * self.width + x)
+        }
+    }
+
+    pub fn cell_center(&self, index: usize) -> Vec2 {
+        let (x, y) = (index % self.width, index / self.width);
+        self.origin + (Vec2::new(x as f32, y as f32) + Vec2::splat(0.5)) * self.cell_size
+    }
+
+    pub fn concentration_at(&self, position: Vec2) -> f32 {
+        self.cell(position)
+            .map(|index| self.concentration[index])
+            .unwrap_or_default()
+    }
+
+    pub fn emit(&mut self, position: Vec2, amount: f32) {
+        if let Some(index) = self.cell(position) {
+            self.concentration[index] += amount;
+        }
+    }
+
+    pub fn sample(&mut self, position: Vec2) {
+        if let Some(index) = self.cell(position) {
+            self.sampled[index] = true;
+        }
+    }
+
+    /// Advances diffusion, advection by the wind and decay. Gas leaves the field at its borders.
+    pub fn step(&mut self, dt: f32) {
+        let dx = self.cell_size;
+        // explicit integration is only stable for small steps, so split long frames
+        let max_dt = 0.2 * dx * dx / self.diffusion.max(f32::EPSILON);
+        let max_dt = max_dt.min(0.5 * dx / self.wind.abs().max_element().max(f32::EPSILON));
+        let steps = (dt / max_dt).ceil().max(1.0) as usize;
+        let dt = dt / steps as f32;
+        for _ in 0..steps {
+            self.substep(dt);
+        }
+    }
+
+    fn substep(&mut self, dt: f32) {
+        let (width, height) = (self.width as isize, self.height as isize);
+        let dx = self.cell_size;
+        let current = &self.concentration;
+        let at = |x: isize, y: isize| {
+            if x < 0 || y < 0 || x >= width || y >= height {
+                0.0
+            } else {
+                current[(y * width + x) as usize]
+            }
+        };
+        let mut next = current.clone();
+        for y in 0..height {
+            for x in 0..width {
+                let c = at(x, y);
+                let laplacian = (at(x - 1, y) + at(x + 1, y) + at(x, y - 1) + at(x, y + 1)
+                    - 4.0 * c)
+                    / (dx * dx);
+                // upwind differences keep the advection from oscillating
+                let gradient_x = if self.wind.x > 0.0 {
+                    c - at(x - 1, y)
+                } else {
+                    at(x + 1, y) - c
+                };
+                let gradient_y = if self.wind.y > 0.0 {
+                    c - at(x, y - 1)
+                } else {
+                    at(x, y + 1) - c
+                };
+                let advection = (self.wind.x * gradient_x + self.wind.y * gradient_y) / dx;
+                next[(y * width + x) as usize] =
+                    (c + (self.diffusion * laplacian - advection - self.decay * c) * dt).max(0.0);
+            }
+        }
+        self.concentration = next;
+    }
+}
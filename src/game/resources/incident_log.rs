@@ -0,0 +1,94 @@
+use bevy::prelude::*;
+
+use crate::game::{components::robot::FaultKind, types::PressureUnit};
+
+/// Sent when an inspection reads a pressure outside of the allowed band.
+#[derive(Debug, Clone)]
+pub struct AlarmEvent {
+    pub manometer: Entity,
+    pub pipe: Option<Entity>,
+    pub value: f32,
+    pub unit: PressureUnit,
+    pub allowed: (f32, f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IncidentKind {
+    Detected(FaultKind),
+    FalseAlarm,
+    Missed(FaultKind),
+}
+
+#[derive(Debug, Clone)]
+pub struct Incident {
+    pub kind: IncidentKind,
+    /// `ItemSeed` of the manometer, as in the inspection log.
+    pub manometer: u64,
+    pub value: Option<f32>,
+    pub unit: PressureUnit,
+    pub timestamp: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlarmResponse {
+    None,
+    Reinspect,
+    MarkForRepair,
+}
+
+impl Default for AlarmResponse {
+    fn default() -> Self {
+        Self::MarkForRepair
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct IncidentLog {
+    pub incidents: Vec<Incident>,
+    pub response: AlarmResponse,
+    pub detected: usize,
+    pub false_alarms: usize,
+    pub missed: usize,
+}
+
+impl IncidentLog {
+    pub fn record(&mut self, incident: Incident) {
+        match incident.kind {
+            IncidentKind::Detected(..) => self.detected += 1,
+            IncidentKind::FalseAlarm => self.false_alarms += 1,
+            IncidentKind::Missed(..) => self.missed += 1,
+        }
+        self.incidents.push(incident);
+    }
+
+    /// Detected faults count for the player, missed ones against.
+    pub fn score(&self) -> i32 {
+        self.detected as i32 - self.missed as i32
+    }
+}
+
+/// Faults injected into the plant, either at random or at scripted times.
+#[derive(Debug, Clone)]
+pub struct FaultSchedule {
+    /// Expected faults per manometer and minute.
+    pub rate: f32,
+    /// Seconds a fault stays active before it counts as missed.
+    pub deadline: f32,
+    pub scripted: Vec<ScriptedFault>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ScriptedFault {
+    pub at: f64,
+    pub kind: FaultKind,
+}
+
+impl Default for FaultSchedule {
+    fn default() -> Self {
+        Self {
+            rate: 0.01,
+            deadline: 180.0,
+            scripted: vec![],
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use std::{fs, io, path::Path};
+
+use crate::game::types::PressureUnit;
+
+/// Items are named by their `ItemSeed`, which stays the same across sessions.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ManometerReading {
+    pub manometer: u64,
+    pub x: f32,
+    pub y: f32,
+    pub pipe: Option<u64>,
+    pub robot: Option<u64>,
+    pub camera: String,
+    pub lens: String,
+    pub value: f32,
+    pub unit: PressureUnit,
+    pub in_band: bool,
+    pub quality: f32,
+    /// Play time in seconds.
+    pub timestamp: f64,
+    /// Unix time in seconds.
+    #[serde(default)]
+    pub recorded_at: u64,
+    pub duration: f32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ThermalReading {
+    pub hot_spot: u64,
+    pub x: f32,
+    pub y: f32,
+    pub value: f32,
+    pub overheated: bool,
+    pub quality: f32,
+    pub timestamp: f64,
+    #[serde(default)]
+    pub recorded_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Csv,
+    Json,
+}
+
+impl ReportFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("csv") => Some(Self::Csv),
+            Some("json") => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct InspectionLog {
+    pub readings: Vec<ManometerReading>,
+    pub thermal: Vec<ThermalReading>,
+}
+
+impl InspectionLog {
+    pub fn record(&mut self, reading: ManometerReading) {
+        self.readings.push(reading);
+    }
+
+    pub fn last(&self) -> Option<&ManometerReading> {
+        self.readings.last()
+    }
+
+    /// Manometer and thermal readings taken so far.
+    pub fn count(&self) -> usize {
+        self.readings.len() + self.thermal.len()
+    }
+
+    pub fn record_thermal(&mut self, reading: ThermalReading) {
+        self.thermal.push(reading);
+    }
+
+    /// Manometer and thermal readings in one table, `kind` tells them apart. Thermal
+    /// readings are in °C, in band unless overheated, and leave the columns they lack empty.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "kind,target,x,y,pipe,robot,camera,lens,value,unit,in_band,quality,timestamp,recorded_at,duration\n",
+        );
+        for reading in &self.readings {
+            csv.push_str(&format!(
+                "manometer,{},{:.1},{:.1},{},{},{},{},{:.3},{},{},{:.3},{:.3},{},{:.3}\n",
+                reading.manometer,
+                reading.x,
+                reading.y,
+                optional_id(reading.pipe),
+                optional_id(reading.robot),
+                csv_field(&reading.camera),
+                csv_field(&reading.lens),
+                reading.value,
+                reading.unit,
+                reading.in_band,
+                reading.quality,
+                reading.timestamp,
+                reading.recorded_at,
+                reading.duration,
+            ));
+        }
+        for reading in &self.thermal {
+            csv.push_str(&format!(
+                "thermal,{},{:.1},{:.1},,,,,{:.3},°C,{},{:.3},{:.3},{},\n",
+                reading.hot_spot,
+                reading.x,
+                reading.y,
+                reading.value,
+                !reading.overheated,
+                reading.quality,
+                reading.timestamp,
+                reading.recorded_at,
+            ));
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&Report {
+            manometers: &self.readings,
+            thermal: &self.thermal,
+        })
+    }
+
+    pub fn report(&self, format: ReportFormat) -> io::Result<String> {
+        match format {
+            ReportFormat::Csv => Ok(self.to_csv()),
+            ReportFormat::Json => self.to_json().map_err(io::Error::from),
+        }
+    }
+
+    /// Writes the report, picking the format from the file extension.
+    pub fn export(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let format = ReportFormat::from_path(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown report format: {}", path.display()),
+            )
+        })?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.report(format)?)
+    }
+}
+
+#[derive(serde::Serialize)]
+struct Report<'a> {
+    manometers: &'a [ManometerReading],
+    thermal: &'a [ThermalReading],
+}
+
+fn optional_id(id: Option<u64>) -> String {
+    id.map(|id| id.to_string()).unwrap_or_default()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains(&[',', '"', '\n'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -1,11 +1,15 @@
 use bevy::{
     prelude::*,
-    reflect::{Reflect, TypeUuid},
+    reflect::{Reflect, Struct, TypeUuid},
 };
 use bevy_asset_loader::AssetCollection;
+#[cfg(feature = "dev")]
 use bevy_inspector_egui::Inspectable;
 
-use crate::game::{components::robot::*, types::ItemType};
+use crate::{
+    consts::PHYSICS_SCALE,
+    game::{components::robot::*, types::ItemType},
+};
 
 use super::sprite_asset::SpriteAsset;
 
@@ -23,6 +27,8 @@ pub struct ItemCollection {
     pub camera_hd: Handle<LoadedItem>,
     #[asset(path = "items/camera_zoom.it")]
     pub camera_zoom: Handle<LoadedItem>,
+    #[asset(path = "items/thermal_camera.it")]
+    pub thermal_camera: Handle<LoadedItem>,
     #[asset(path = "items/camera_lens_wide.it")]
     pub camera_lens_wide: Handle<LoadedItem>,
     #[asset(path = "items/camera_lens_telephoto.it")]
@@ -31,6 +37,13 @@ pub struct ItemCollection {
     #[asset(path = "items/simple_battery.it")]
     pub simple_battery: Handle<LoadedItem>,
 
+    #[asset(path = "items/lidar.it")]
+    pub lidar: Handle<LoadedItem>,
+    #[asset(path = "items/ultrasonic_sensor.it")]
+    pub ultrasonic_sensor: Handle<LoadedItem>,
+    #[asset(path = "items/gas_sensor.it")]
+    pub gas_sensor: Handle<LoadedItem>,
+
     #[asset(path = "items/interaction_point.it")]
     pub interaction_point: Handle<LoadedItem>,
     #[asset(path = "items/waypoint_marker.it")]
@@ -48,8 +61,19 @@ pub struct ItemCollection {
     #[asset(path = "items/gray_pipe_split.it")]
     pub gray_pipe_split: Handle<LoadedItem>,
 
+    #[asset(path = "items/wall.it")]
+    pub wall: Handle<LoadedItem>,
+    #[asset(path = "items/charging_station.it")]
+    pub charging_station: Handle<LoadedItem>,
+    #[asset(path = "items/door.it")]
+    pub door: Handle<LoadedItem>,
+    #[asset(path = "items/gate.it")]
+    pub gate: Handle<LoadedItem>,
+
     #[asset(path = "items/simple_manometer_icon.it")]
     pub simple_manometer_icon: Handle<LoadedItem>,
+    #[asset(path = "items/hot_spot.it")]
+    pub hot_spot: Handle<LoadedItem>,
 
     #[asset(path = "items/fancy_manometer_pointer.it")]
     pub fancy_manometer_pointer: Handle<LoadedItem>,
@@ -61,6 +85,15 @@ pub struct ItemCollection {
     pub simple_manometer_background: Handle<LoadedItem>,
 }
 
+impl ItemCollection {
+    /// The item named like its field, as in scenarios and save games.
+    pub fn handle(&self, name: &str) -> Option<Handle<LoadedItem>> {
+        self.field(name)
+            .and_then(|field| field.downcast_ref::<Handle<LoadedItem>>())
+            .cloned()
+    }
+}
+
 #[derive(serde::Deserialize, TypeUuid, Debug, Clone)]
 #[uuid = "1df82c01-9c71-4fa8-adc4-78c5822268f1"]
 pub struct LoadedItem {
@@ -76,10 +109,30 @@ pub struct LoadedItem {
     pub attachment_points: AttachmentMap<AttachmentPoint>,
     #[serde(default)]
     pub joint_type: JointType,
+    /// In kilograms, derived from the sprite area when missing.
+    #[serde(default)]
+    pub mass: f32,
     pub sprite: SpriteAsset,
 }
 
-#[derive(serde::Deserialize, Debug, Clone, Default, Inspectable)]
+impl LoadedItem {
+    pub fn mass(&self) -> f32 {
+        if self.mass > 0.0 {
+            self.mass
+        } else {
+            self.sprite.size.0 * self.sprite.size.1 / (20. * PHYSICS_SCALE)
+                * self.collider_area()
+        }
+    }
+
+    /// Area of the collider in physics units.
+    pub fn collider_area(&self) -> f32 {
+        (self.sprite.size.0 / PHYSICS_SCALE) * (self.sprite.size.1 / PHYSICS_SCALE)
+    }
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct AttachmentPoint {
     pub position: (f32, f32, f32),
     pub rotation: f32,
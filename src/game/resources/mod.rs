@@ -4,4 +4,15 @@ pub mod sprite_asset;
 pub mod terrain_collider;
 pub mod ui;
 pub mod robot_commands;
-pub mod pipe_line;
\ No newline at end of file
+pub mod pipe_line;
+pub mod exploration_map;
+pub mod inspection_log;pub mod compliance;
+pub mod incident_log;
+pub mod gas_field;
+pub mod world_seed;
+pub mod pipe_network;
+pub mod facility_map;
+pub mod surface_map;
+pub mod scenario;
+pub mod save_game;
+pub mod offline;
@@ -0,0 +1,60 @@
+const MAX_AWAY: f64 = 8.0 * 3600.0;
+const STEP: f64 = 10.0;
+const INSPECTION_TIME: f64 = 30.0;
+const POWER: f32 = 40.0;
+
+/// Progress made by the autonomous robots while the game was closed, simulated coarsely
+/// when a save is loaded.
+pub struct OfflineProgress {
+    /// Longest time away that counts, in seconds.
+    pub max_away: f64,
+    /// Seconds per simulation step.
+    pub step: f64,
+    /// Seconds a robot needs to drive to a target and inspect it.
+    pub inspection_time: f64,
+    /// Battery charge a working robot draws per second.
+    pub power: f32,
+    /// Seconds away of a loaded save, simulated once its items are restored.
+    pub pending: Option<f64>,
+    pub report: Option<OfflineReport>,
+}
+
+impl Default for OfflineProgress {
+    fn default() -> Self {
+        Self {
+            max_away: MAX_AWAY,
+            step: STEP,
+            inspection_time: INSPECTION_TIME,
+            power: POWER,
+            pending: None,
+            report: None,
+        }
+    }
+}
+
+/// What is shown in "While you were away".
+#[derive(Debug, Clone, Default)]
+pub struct OfflineReport {
+    pub away: f64,
+    /// Time that was simulated, `away` capped at `max_away`.
+    pub simulated: f64,
+    pub robots: usize,
+    pub manometers_inspected: usize,
+    pub hot_spots_inspected: usize,
+    pub energy_used: f32,
+    pub energy_charged: f32,
+    /// Robots that ran out of charge with no charging station around.
+    pub robots_drained: usize,
+    pub faults_detected: usize,
+    pub faults_missed: usize,
+    /// Faults that began while away and are still there on return.
+    pub faults_open: usize,
+    /// Targets that were due or overdue on return.
+    pub targets_due: usize,
+}
+
+impl OfflineReport {
+    pub fn is_capped(&self) -> bool {
+        self.simulated < self.away
+    }
+}
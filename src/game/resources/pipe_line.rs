@@ -1,22 +1,103 @@
 use bevy::{prelude::*, render::pass::TextureAttachment};
+#[cfg(feature = "dev")]
 use bevy_inspector_egui::Inspectable;
 
 use crate::game::components::robot::AttachmentPointId;
 
+#[derive(Clone)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub struct PipeLineConfig {
+    /// Maximum number of segments in the layout.
+    pub budget: usize,
+    pub bounds_min: Vec2,
+    pub bounds_max: Vec2,
+    pub straight: f32,
+    pub bent: f32,
+    pub split: f32,
+    pub manometer_density: f32,
+    pub hot_spot_density: f32,
+    pub leak_density: f32,
+    pub valve_density: f32,
+}
+
+impl Default for PipeLineConfig {
+    fn default() -> Self {
+        Self {
+            budget: 60,
+            bounds_min: Vec2::new(-1600.0, -1600.0),
+            bounds_max: Vec2::new(1600.0, 1600.0),
+            straight: 0.5,
+            bent: 0.25,
+            split: 0.25,
+            manometer_density: 0.5,
+            hot_spot_density: 0.15,
+            leak_density: 0.05,
+            valve_density: 0.1,
+        }
+    }
+}
+
+impl PipeLineConfig {
+    pub fn in_bounds(&self, position: Vec2) -> bool {
+        position.x >= self.bounds_min.x
+            && position.y >= self.bounds_min.y
+            && position.x <= self.bounds_max.x
+            && position.y <= self.bounds_max.y
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipeSegment {
+    Straight,
+    Bent,
+    Split,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct PipeLineReport {
+    pub straight: usize,
+    pub bent: usize,
+    pub split: usize,
+    pub manometers: usize,
+    pub hot_spots: usize,
+    pub leaks: usize,
+    pub valves: usize,
+    pub capped: usize,
+}
+
+impl PipeLineReport {
+    pub fn segments(&self) -> usize {
+        self.straight + self.bent + self.split
+    }
+}
+
 #[derive(Default, Clone)]
 pub struct PipeLine {
     pub root: Option<Entity>,
     pub current: Vec<(Entity, AttachmentPointId)>,
     pub timer: Timer,
+    pub config: PipeLineConfig,
+    pub report: PipeLineReport,
+    pub finished: bool,
 }
 
 impl PipeLine {
     pub fn new(root: Entity, aid: AttachmentPointId) -> Self {
-        Self {
-            root: Some(root),
-            current: vec![(root, aid)],
-            timer: Timer::from_seconds(0.5, true),
-        }
+        let mut pipe_line = Self::default();
+        pipe_line.start(root, aid);
+        pipe_line
+    }
+
+    /// Starts a new layout from the root segment, keeping the configuration.
+    pub fn start(&mut self, root: Entity, aid: AttachmentPointId) {
+        self.root = Some(root);
+        self.current = vec![(root, aid)];
+        self.timer = Timer::from_seconds(0.5, true);
+        self.report = PipeLineReport {
+            straight: 1,
+            ..Default::default()
+        };
+        self.finished = false;
     }
 
     pub fn add_pipe(
@@ -35,4 +116,8 @@ impl PipeLine {
         //     self.current.swap_remove(index);
         // }
     }
+
+    pub fn has_budget(&self) -> bool {
+        self.report.segments() < self.config.budget
+    }
 }
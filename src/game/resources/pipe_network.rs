@@ -0,0 +1,104 @@
+use bevy::{prelude::*, utils::HashMap};
+
+/// Conductance between two connected segments.
+pub const PIPE_CONDUCTANCE: f32 = 10.0;
+/// Consumption at an open endpoint.
+pub const SINK_CONDUCTANCE: f32 = 0.01;
+/// Conductance to the ambient per unit of leak rate.
+pub const LEAK_CONDUCTANCE: f32 = 0.05;
+
+#[derive(Debug, Default, Clone)]
+pub struct PipeNode {
+    pub neighbours: Vec<Entity>,
+    /// Fixed head of a source segment.
+    pub source: Option<f32>,
+    /// Conductance to the ambient from sinks and leaks.
+    pub outflow: f32,
+    pub open: bool,
+    pub head: f32,
+}
+
+impl PipeNode {
+    pub fn is_junction(&self) -> bool {
+        self.neighbours.len() > 2
+    }
+}
+
+/// Pipe segments connected through their `Next` and `Previous` attachments, with the
+/// head relative to the source solved for the steady state.
+#[derive(Debug, Default, Clone)]
+pub struct PipeNetwork {
+    pub nodes: HashMap<Entity, PipeNode>,
+}
+
+impl PipeNetwork {
+    /// Replaces the node, keeping the previous head so the solver starts warm.
+    pub fn insert(&mut self, entity: Entity, mut node: PipeNode) {
+        if let Some(previous) = self.nodes.get(&entity) {
+            node.head = previous.head;
+        }
+        self.nodes.insert(entity, node);
+    }
+
+    pub fn connect(&mut self, a: Entity, b: Entity) {
+        for (from, to) in [(a, b), (b, a)] {
+            if let Some(node) = self.nodes.get_mut(&from) {
+                if !node.neighbours.contains(&to) {
+                    node.neighbours.push(to);
+                }
+            }
+        }
+    }
+
+    pub fn head(&self, entity: Entity) -> Option<f32> {
+        self.nodes.get(&entity).map(|node| node.head)
+    }
+
+    pub fn junctions(&self) -> usize {
+        self.nodes
+            .values()
+            .filter(|node| node.is_junction())
+            .count()
+    }
+
+    /// Conductance and head of a neighbour, a closed valve on either side blocks the flow.
+    fn neighbour(&self, node: &PipeNode, neighbour: Entity) -> (f32, f32) {
+        match self.nodes.get(&neighbour) {
+            Some(other) if node.open && other.open => (PIPE_CONDUCTANCE, other.head),
+            _ => (0.0, 0.0),
+        }
+    }
+
+    /// Gauss-Seidel sweeps over the nodes, returns the largest change of the last sweep.
+    pub fn solve(&mut self, iterations: usize) -> f32 {
+        let entities = self.nodes.keys().copied().collect::<Vec<_>>();
+        let mut change = 0.0;
+        for _ in 0..iterations {
+            change = 0.0;
+            for entity in &entities {
+                let node = &self.nodes[entity];
+                let head = match node.source {
+                    Some(head) => head,
+                    None => {
+                        let (flow, conductance) = node.neighbours.iter().fold(
+                            (0.0, node.outflow),
+                            |(flow, conductance), neighbour| {
+                                let (g, head) = self.neighbour(node, *neighbour);
+                                (flow + g * head, conductance + g)
+                            },
+                        );
+                        if conductance > 0.0 {
+                            flow / conductance
+                        } else {
+                            0.0
+                        }
+                    }
+                };
+                let node = self.nodes.get_mut(entity).unwrap();
+                change = f32::max(change, (node.head - head).abs());
+                node.head = head;
+            }
+        }
+        change
+    }
+}
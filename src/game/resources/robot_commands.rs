@@ -8,6 +8,8 @@ use crate::game::components::robot::ParentEntity;
 #[derive(Debug, Default, Clone)]
 pub struct RobotCommands {
     pub queue: Vec<RobotCommand>,
+    /// Battery charge drawn by all commands so far.
+    pub energy_used: f32,
 }
 
 impl RobotCommands {
@@ -40,4 +42,11 @@ pub enum RobotCommandType {
         position: f32,
         limits: Range<f32>,
     },
+    Sense {
+        entity: Entity,
+    },
+    OperateDoor {
+        door: Entity,
+        open: bool,
+    },
 }
@@ -0,0 +1,309 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{prelude::*, utils::Duration};
+use serde::{Deserialize, Serialize};
+
+use crate::game::{
+    components::robot::{AttachmentPointId, FaultKind},
+    resources::inspection_log::{ManometerReading, ThermalReading},
+};
+
+/// Bumped whenever the format changes, older saves run through `MIGRATIONS`.
+pub const SAVE_VERSION: u32 = 2;
+
+/// Upgrades a save from the version it is listed with to the next one. Fields that are
+/// added later need `#[serde(default)]`, so that older saves still parse and a migration
+/// can fill them in.
+type Migration = fn(&mut SaveGame);
+const MIGRATIONS: &[(u32, Migration)] = &[(1, from_v1)];
+
+/// Version 1 did not save valves, faults and the explored area. Pipes that had a valve
+/// come back without one, and the fog covers the whole map again.
+fn from_v1(save_game: &mut SaveGame) {
+    save_game.explored.clear();
+}
+
+const SAVE_DIR: &str = "saves";
+const SAVE_SLOTS: usize = 3;
+const AUTOSAVE_INTERVAL: f32 = 300.0;
+/// Frames to wait for the parts of a loaded save to attach before giving up on them.
+const RESTORE_FRAMES: usize = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveSlot {
+    Auto,
+    Manual(usize),
+}
+
+impl SaveSlot {
+    pub fn file_name(&self) -> String {
+        match self {
+            Self::Auto => "autosave.ron".to_string(),
+            Self::Manual(slot) => format!("slot{}.ron", slot + 1),
+        }
+    }
+}
+
+impl std::fmt::Display for SaveSlot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Auto => write!(f, "Autosave"),
+            Self::Manual(slot) => write!(f, "Slot {}", slot + 1),
+        }
+    }
+}
+
+/// Progress of a manometer or hot spot inspection.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SavedInspection {
+    pub progress: f32,
+    pub quality: f32,
+    pub reading: Option<f32>,
+    pub last_inspected: Option<f64>,
+}
+
+/// A fault a manometer shows, in play time.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct SavedFault {
+    pub kind: FaultKind,
+    pub offset: f32,
+    pub started: f64,
+    pub deadline: f64,
+}
+
+/// An item and everything attached to it, named like the `ItemCollection` fields.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SavedItem {
+    pub item: String,
+    /// Where it is attached to its parent, roots have none.
+    #[serde(default)]
+    pub at: Option<AttachmentPointId>,
+    /// Rebuilds the randomized components as they were, pressure ranges for example.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub charge: Option<f32>,
+    #[serde(default)]
+    pub manometer: Option<SavedInspection>,
+    #[serde(default)]
+    pub hot_spot: Option<SavedInspection>,
+    #[serde(default)]
+    pub open: Option<bool>,
+    /// Whether the valve of a pipe is open.
+    #[serde(default)]
+    pub valve: Option<bool>,
+    #[serde(default)]
+    pub fault: Option<SavedFault>,
+    #[serde(default)]
+    pub parts: Vec<SavedItem>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub struct SavedAutopilot {
+    pub enabled: bool,
+    pub prefer_cheap_surfaces: bool,
+}
+
+/// A robot, pipe line, structure or waypoint and where it stands.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SavedRoot {
+    pub position: Vec3,
+    /// Counter-clockwise, in degrees.
+    pub rotation: f32,
+    #[serde(default)]
+    pub autopilot: Option<SavedAutopilot>,
+    #[serde(default)]
+    pub pipe_source: Option<f32>,
+    pub item: SavedItem,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SavedStatistics {
+    pub manometers_inspected: usize,
+    #[serde(default)]
+    pub hot_spots_inspected: usize,
+    pub energy_used: f32,
+    #[serde(default)]
+    pub detected: usize,
+    #[serde(default)]
+    pub false_alarms: usize,
+    #[serde(default)]
+    pub missed: usize,
+    #[serde(default)]
+    pub readings: Vec<ManometerReading>,
+    #[serde(default)]
+    pub thermal: Vec<ThermalReading>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SaveGame {
+    pub version: u32,
+    /// Unix time in seconds.
+    pub saved_at: u64,
+    /// Seconds played over all sessions, the timestamps in the save are play time too.
+    pub play_time: f64,
+    pub seed: u64,
+    #[serde(default)]
+    pub map: Option<PathBuf>,
+    #[serde(default)]
+    pub items: Vec<SavedRoot>,
+    #[serde(default)]
+    pub statistics: SavedStatistics,
+    /// Explored cells of the exploration map.
+    #[serde(default)]
+    pub explored: Vec<usize>,
+}
+
+impl SaveGame {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut save_game: SaveGame = ron::de::from_str(&fs::read_to_string(path)?)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        if save_game.version > SAVE_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("saved by a newer version ({})", save_game.version),
+            ));
+        }
+        for (version, migration) in MIGRATIONS {
+            if save_game.version == *version {
+                migration(&mut save_game);
+                save_game.version += 1;
+            }
+        }
+        save_game.version = SAVE_VERSION;
+        Ok(save_game)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let ron = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        fs::write(path, ron)
+    }
+
+    /// Seconds of wall clock time since the game was saved.
+    pub fn age(&self) -> u64 {
+        unix_time().saturating_sub(self.saved_at)
+    }
+
+    pub fn robots(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|root| root.item.item.ends_with("_body"))
+            .count()
+    }
+}
+
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// What is shown about a slot without loading it.
+#[derive(Debug, Clone)]
+pub struct SlotSummary {
+    pub saved_at: u64,
+    pub play_time: f64,
+    pub robots: usize,
+    pub manometers_inspected: usize,
+    pub hot_spots_inspected: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveRequest {
+    Save(SaveSlot),
+    Load(SaveSlot),
+    /// Loads the slot when the game starts, and lets the robots catch up on the time the
+    /// game was closed.
+    Resume(SaveSlot),
+}
+
+/// Save slots on disk, the autosave timer and a restore in progress.
+pub struct SaveGames {
+    pub dir: PathBuf,
+    pub slots: usize,
+    pub autosave: Timer,
+    pub request: Option<SaveRequest>,
+    pub summaries: Vec<(SaveSlot, Option<SlotSummary>)>,
+    /// Items of a loaded save by the entity they were spawned as, restored once their
+    /// parts are attached.
+    pub restoring: Vec<(Entity, SavedItem)>,
+    /// Frames left until restoring gives up on items that never attach.
+    pub restore_frames: usize,
+    /// Play time minus the seconds since startup, changes when a save is loaded.
+    pub time_offset: f64,
+    pub status: String,
+}
+
+impl Default for SaveGames {
+    fn default() -> Self {
+        let mut save_games = Self {
+            dir: PathBuf::from(SAVE_DIR),
+            slots: SAVE_SLOTS,
+            autosave: Timer::new(Duration::from_secs_f32(AUTOSAVE_INTERVAL), true),
+            request: None,
+            summaries: vec![],
+            restoring: vec![],
+            restore_frames: 0,
+            time_offset: 0.0,
+            status: String::new(),
+        };
+        save_games.refresh();
+        save_games
+    }
+}
+
+impl SaveGames {
+    pub fn start_restoring(&mut self) {
+        self.restoring.clear();
+        self.restore_frames = RESTORE_FRAMES;
+    }
+
+    pub fn play_time(&self, time: &Time) -> f64 {
+        time.seconds_since_startup() + self.time_offset
+    }
+
+    pub fn path(&self, slot: SaveSlot) -> PathBuf {
+        self.dir.join(slot.file_name())
+    }
+
+    pub fn all_slots(&self) -> impl Iterator<Item = SaveSlot> {
+        std::iter::once(SaveSlot::Auto).chain((0..self.slots).map(SaveSlot::Manual))
+    }
+
+    /// Reads the summaries of all slots again.
+    pub fn refresh(&mut self) {
+        self.summaries = self
+            .all_slots()
+            .map(|slot| {
+                let summary = SaveGame::load(self.path(slot))
+                    .ok()
+                    .map(|save_game| SlotSummary {
+                        saved_at: save_game.saved_at,
+                        play_time: save_game.play_time,
+                        robots: save_game.robots(),
+                        manometers_inspected: save_game.statistics.manometers_inspected,
+                        hot_spots_inspected: save_game.statistics.hot_spots_inspected,
+                    });
+                (slot, summary)
+            })
+            .collect();
+    }
+
+    /// The most recently written slot, if any.
+    pub fn latest(&self) -> Option<SaveSlot> {
+        self.summaries
+            .iter()
+            .filter_map(|(slot, summary)| summary.as_ref().map(|summary| (slot, summary)))
+            .max_by_key(|(_, summary)| summary.saved_at)
+            .map(|(slot, _)| *slot)
+    }
+}
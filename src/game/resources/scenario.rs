@@ -0,0 +1,138 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::game::components::robot::AttachmentPointId;
+
+/// A robot design, made of items named like the `ItemCollection` fields.
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct ScenarioPart {
+    pub item: String,
+    pub at: AttachmentPointId,
+    #[serde(default)]
+    pub parts: Vec<ScenarioPart>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct ScenarioRobot {
+    #[serde(default = "ScenarioRobot::default_body")]
+    pub body: String,
+    /// Defaults to the first robot start of the map.
+    #[serde(default)]
+    pub position: Option<Vec2>,
+    /// Counter-clockwise, in degrees.
+    #[serde(default)]
+    pub rotation: f32,
+    #[serde(default)]
+    pub autopilot: bool,
+    #[serde(default)]
+    pub prefer_cheap_surfaces: bool,
+    #[serde(default)]
+    pub parts: Vec<ScenarioPart>,
+}
+
+impl ScenarioRobot {
+    fn default_body() -> String {
+        "simple_body".to_string()
+    }
+}
+
+/// What the headless runner sets up: an optional map, the robots to evaluate and the
+/// waypoints their autopilots drive to.
+#[derive(serde::Deserialize, Debug, Clone, Default)]
+pub struct Scenario {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub map: Option<PathBuf>,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub robots: Vec<ScenarioRobot>,
+    #[serde(default)]
+    pub waypoints: Vec<Vec2>,
+}
+
+impl Scenario {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let scenario = serde_json::from_str(&fs::read_to_string(path)?)?;
+        Ok(scenario)
+    }
+}
+
+/// Clock and measurements of a headless run. Once in game, time advances by exactly one
+/// timestep per frame, however long the frame took.
+pub struct SimRun {
+    pub timestep: f32,
+    pub duration: f32,
+    /// Metrics are written here, or to stdout.
+    pub output: Option<PathBuf>,
+    pub clock: Time,
+    pub start: Instant,
+    pub frames: u64,
+    /// Seconds since startup when the scenario was spawned.
+    pub started: Option<f64>,
+    pub distance: f32,
+    pub collisions: usize,
+    pub positions: HashMap<Entity, Vec2>,
+}
+
+impl SimRun {
+    pub fn new(timestep: f32, duration: f32) -> Self {
+        let mut clock = Time::default();
+        let start = Instant::now();
+        clock.update_with_instant(start);
+        Self {
+            timestep,
+            duration,
+            output: None,
+            clock,
+            start,
+            frames: 0,
+            started: None,
+            distance: 0.0,
+            collisions: 0,
+            positions: HashMap::default(),
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.frames += 1;
+        let elapsed = Duration::from_secs_f64(self.frames as f64 * self.timestep as f64);
+        self.clock.update_with_instant(self.start + elapsed);
+    }
+
+    /// Simulated seconds since the scenario was spawned.
+    pub fn elapsed(&self) -> f64 {
+        self.started
+            .map_or(0.0, |started| self.clock.seconds_since_startup() - started)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.started.is_some() && self.elapsed() >= self.duration as f64
+    }
+}
+
+#[derive(serde::Serialize, Debug, Clone, Default)]
+pub struct SimMetrics {
+    pub scenario: String,
+    pub seed: u64,
+    pub timestep: f32,
+    pub duration: f64,
+    pub frames: u64,
+    /// Distinct manometers with at least one logged reading.
+    pub manometers_inspected: usize,
+    /// Distinct hot spots with at least one logged reading.
+    pub hot_spots_inspected: usize,
+    pub readings: usize,
+    pub thermal_readings: usize,
+    pub compliance: f32,
+    pub energy_used: f32,
+    /// In pixels, summed over all robots.
+    pub distance_driven: f32,
+    pub collisions: usize,
+}
@@ -1,8 +1,10 @@
 use bevy::{prelude::*, reflect::TypeUuid};
+#[cfg(feature = "dev")]
 use bevy_inspector_egui::Inspectable;
 use std::fmt::Debug;
 
-#[derive(serde::Deserialize, TypeUuid, Inspectable, Debug, Reflect, Clone, Default)]
+#[derive(serde::Deserialize, TypeUuid, Debug, Reflect, Clone, Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 #[uuid = "1df82c01-9c71-4fa8-adc4-78c5822268f8"]
 pub struct SpriteAsset {
     pub size: (f32, f32),
@@ -0,0 +1,237 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use bevy::prelude::*;
+
+use crate::game::{
+    resources::{facility_map::FacilityMap, terrain_collider::TerrainShape},
+    types::SurfaceType,
+};
+
+/// Surface on a grid covering the same area as the exploration map. Tiles and painted
+/// polygons are rasterized into it, everything else is concrete.
+pub struct SurfaceMap {
+    pub cell_size: f32,
+    pub origin: Vec2,
+    pub width: usize,
+    pub height: usize,
+    pub surfaces: Vec<SurfaceType>,
+}
+
+impl Default for SurfaceMap {
+    fn default() -> Self {
+        Self::new(32.0, Vec2::new(-1600.0, -1600.0), 100, 100)
+    }
+}
+
+impl SurfaceMap {
+    pub fn new(cell_size: f32, origin: Vec2, width: usize, height: usize) -> Self {
+        Self {
+            cell_size,
+            origin,
+            width,
+            height,
+            surfaces: vec![SurfaceType::default(); width * height],
+        }
+    }
+
+    pub fn cell(&self, position: Vec2) -> Option<usize> {
+        let local = (position - self.origin) / self.cell_size;
+        if local.x < 0.0 || local.y < 0.0 {
+            return None;
+        }
+        let (x, y) = (local.x as usize, local.y as usize);
+        if x >= self.width || y >= self.height {
+            None
+        } else {
+            Some(y * self.width + x)
+        }
+    }
+
+    pub fn cell_center(&self, index: usize) -> Vec2 {
+        let (x, y) = (index % self.width, index / self.width);
+        self.origin + (Vec2::new(x as f32, y as f32) + Vec2::splat(0.5)) * self.cell_size
+    }
+
+    pub fn surface_at(&self, position: Vec2) -> SurfaceType {
+        self.cell(position)
+            .map(|index| self.surfaces[index])
+            .unwrap_or_default()
+    }
+
+    /// Cost of driving across a cell, relative to concrete.
+    pub fn cost(&self, index: usize) -> f32 {
+        let properties = self.surfaces[index].properties();
+        properties.power_cost * properties.rolling_resistance
+    }
+
+    pub fn rebuild(&mut self, facility_map: &FacilityMap, shapes: &[TerrainShape]) {
+        self.surfaces
+            .iter_mut()
+            .for_each(|surface| *surface = SurfaceType::default());
+
+        // only tile layers named after a surface type set the ground, others are decoration
+        let grid = facility_map.grid_size;
+        let bottom = facility_map.origin.y - facility_map.size.y as f32 * grid;
+        for layer in &facility_map.layers {
+            let surface = match SurfaceType::from_name(&layer.name) {
+                Some(surface) => surface,
+                None => continue,
+            };
+            for tile in &layer.tiles {
+                let min = Vec2::new(
+                    facility_map.origin.x + tile.position.x as f32 * grid,
+                    bottom + tile.position.y as f32 * grid,
+                );
+                self.fill(min, min + Vec2::splat(grid), |_| true, surface);
+            }
+        }
+
+        for shape in shapes {
+            let surface = match shape.surface {
+                Some(surface) if shape.vertices.len() > 2 => surface,
+                _ => continue,
+            };
+            let min = shape
+                .vertices
+                .iter()
+                .copied()
+                .fold(Vec2::splat(f32::MAX), Vec2::min);
+            let max = shape
+                .vertices
+                .iter()
+                .copied()
+                .fold(Vec2::splat(f32::MIN), Vec2::max);
+            self.fill(min, max, |point| contains(&shape.vertices, point), surface);
+        }
+    }
+
+    fn fill(&mut self, min: Vec2, max: Vec2, inside: impl Fn(Vec2) -> bool, surface: SurfaceType) {
+        let cells = |value: f32, origin: f32, count: usize| {
+            (((value - origin) / self.cell_size).floor().max(0.0) as usize).min(count)
+        };
+        let (x0, x1) = (
+            cells(min.x, self.origin.x, self.width),
+            cells(max.x, self.origin.x, self.width - 1) + 1,
+        );
+        let (y0, y1) = (
+            cells(min.y, self.origin.y, self.height),
+            cells(max.y, self.origin.y, self.height - 1) + 1,
+        );
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let index = y * self.width + x;
+                let center = self.cell_center(index);
+                if center.cmpge(min).all() && center.cmple(max).all() && inside(center) {
+                    self.surfaces[index] = surface;
+                }
+            }
+        }
+    }
+
+    /// A* over the cells, weighted by the surface cost. Returns the corners of the route
+    /// ending at the target, or an empty route when either end is off the map.
+    pub fn route(&self, from: Vec2, to: Vec2) -> Vec<Vec2> {
+        let (start, goal) = match (self.cell(from), self.cell(to)) {
+            (Some(start), Some(goal)) => (start, goal),
+            _ => return vec![],
+        };
+        // costs are kept in thousandths to order them in the heap
+        let scale = 1000.0;
+        let heuristic = |index: usize| {
+            (self.cell_center(index).distance(self.cell_center(goal)) / self.cell_size * scale)
+                as u32
+        };
+        let mut costs = vec![u32::MAX; self.surfaces.len()];
+        let mut previous = vec![usize::MAX; self.surfaces.len()];
+        let mut open = BinaryHeap::new();
+        costs[start] = 0;
+        open.push(Reverse((heuristic(start), start)));
+
+        while let Some(Reverse((_, index))) = open.pop() {
+            if index == goal {
+                break;
+            }
+            let (x, y) = ((index % self.width) as i32, (index / self.width) as i32);
+            for (dx, dy) in [
+                (-1, 0),
+                (1, 0),
+                (0, -1),
+                (0, 1),
+                (-1, -1),
+                (1, -1),
+                (-1, 1),
+                (1, 1),
+            ] {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= self.width as i32 || ny >= self.height as i32 {
+                    continue;
+                }
+                let neighbour = ny as usize * self.width + nx as usize;
+                let length = if dx != 0 && dy != 0 {
+                    std::f32::consts::SQRT_2
+                } else {
+                    1.0
+                };
+                let step =
+                    (length * (self.cost(index) + self.cost(neighbour)) / 2.0 * scale) as u32;
+                let cost = costs[index].saturating_add(step);
+                if cost < costs[neighbour] {
+                    costs[neighbour] = cost;
+                    previous[neighbour] = index;
+                    open.push(Reverse((
+                        cost.saturating_add(heuristic(neighbour)),
+                        neighbour,
+                    )));
+                }
+            }
+        }
+        if costs[goal] == u32::MAX {
+            return vec![];
+        }
+
+        let mut cells = vec![goal];
+        while let Some(&index) = cells.last() {
+            if index == start {
+                break;
+            }
+            cells.push(previous[index]);
+        }
+        cells.reverse();
+
+        // keep the corners only
+        let mut route = vec![];
+        for (i, index) in cells.iter().enumerate().skip(1) {
+            let last = i + 1 == cells.len();
+            let turns = !last && {
+                let direction = |a: usize, b: usize| {
+                    (
+                        (b % self.width) as i32 - (a % self.width) as i32,
+                        (b / self.width) as i32 - (a / self.width) as i32,
+                    )
+                };
+                direction(cells[i - 1], *index) != direction(*index, cells[i + 1])
+            };
+            if turns {
+                route.push(self.cell_center(*index));
+            }
+        }
+        route.push(to);
+        route
+    }
+}
+
+/// Even-odd rule.
+fn contains(polygon: &[Vec2], point: Vec2) -> bool {
+    let mut inside = false;
+    let mut j = polygon.len() - 1;
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[j]);
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
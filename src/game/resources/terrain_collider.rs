@@ -1,7 +1,267 @@
+use std::{fs, io, path::Path};
+
 use bevy::math::Vec2;
+#[cfg(feature = "dev")]
 use bevy_inspector_egui::Inspectable;
 
-#[derive(Default, Inspectable)]
+use crate::game::types::SurfaceType;
+
+const MAX_UNDO: usize = 100;
+
+#[derive(Default)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct TerrainCollider {
+    pub index: usize,
     pub vertices: Vec<Vec2>,
-}
\ No newline at end of file
+    pub closed: bool,
+    /// Surface zones are painted on the ground and do not collide.
+    pub zone: bool,
+}
+
+/// A polyline, or a polygon when closed, in world coordinates. Closed polygons with a
+/// surface are zones instead of walls.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct TerrainShape {
+    pub vertices: Vec<Vec2>,
+    #[serde(default)]
+    pub closed: bool,
+    #[serde(default)]
+    pub surface: Option<SurfaceType>,
+}
+
+impl TerrainShape {
+    pub fn is_zone(&self) -> bool {
+        self.closed && self.surface.is_some()
+    }
+
+    pub fn segments(&self) -> impl Iterator<Item = (usize, Vec2, Vec2)> + '_ {
+        let count = self.vertices.len();
+        let segments = if self.closed && count > 2 {
+            count
+        } else {
+            count.saturating_sub(1)
+        };
+        (0..segments).map(move |i| (i, self.vertices[i], self.vertices[(i + 1) % count]))
+    }
+}
+
+pub struct TerrainEditor {
+    pub enabled: bool,
+    /// Grid to snap vertices to, disabled at zero.
+    pub snap: f32,
+    pub shapes: Vec<TerrainShape>,
+    /// Shape that new vertices are appended to.
+    pub active: Option<usize>,
+    /// Shape and vertex index.
+    pub selected: Option<(usize, usize)>,
+    pub dragging: bool,
+    /// Whether the current drag was remembered for undo.
+    pub drag_saved: bool,
+    pub history: Vec<Vec<TerrainShape>>,
+    pub changed: bool,
+    pub status: String,
+}
+
+impl Default for TerrainEditor {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            snap: 8.0,
+            shapes: vec![],
+            active: None,
+            selected: None,
+            dragging: false,
+            drag_saved: false,
+            history: vec![],
+            changed: false,
+            status: String::new(),
+        }
+    }
+}
+
+impl TerrainEditor {
+    pub fn snapped(&self, position: Vec2) -> Vec2 {
+        if self.snap > 0.0 {
+            (position / self.snap).round() * self.snap
+        } else {
+            position
+        }
+    }
+
+    /// Remembers the current shapes, call before every edit.
+    pub fn checkpoint(&mut self) {
+        self.history.push(self.shapes.clone());
+        if self.history.len() > MAX_UNDO {
+            self.history.remove(0);
+        }
+    }
+
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            Some(shapes) => {
+                self.set_shapes(shapes);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn set_shapes(&mut self, shapes: Vec<TerrainShape>) {
+        self.shapes = shapes;
+        self.active = None;
+        self.selected = None;
+        self.dragging = false;
+        self.changed = true;
+    }
+
+    pub fn vertex(&self, (shape, vertex): (usize, usize)) -> Option<Vec2> {
+        self.shapes
+            .get(shape)
+            .and_then(|shape| shape.vertices.get(vertex))
+            .copied()
+    }
+
+    pub fn vertex_at(&self, position: Vec2, radius: f32) -> Option<(usize, usize)> {
+        self.shapes
+            .iter()
+            .enumerate()
+            .flat_map(|(s, shape)| {
+                shape
+                    .vertices
+                    .iter()
+                    .enumerate()
+                    .map(move |(v, vertex)| ((s, v), vertex.distance(position)))
+            })
+            .filter(|(_, distance)| *distance <= radius)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(index, _)| index)
+    }
+
+    /// Finds the segment closest to the position, returns the shape and the index of the
+    /// segment's first vertex.
+    pub fn segment_at(&self, position: Vec2, radius: f32) -> Option<(usize, usize)> {
+        self.shapes
+            .iter()
+            .enumerate()
+            .flat_map(|(s, shape)| {
+                shape
+                    .segments()
+                    .map(move |(i, a, b)| ((s, i), distance_to_segment(position, a, b)))
+            })
+            .filter(|(_, distance)| *distance <= radius)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(index, _)| index)
+    }
+
+    pub fn add_vertex(&mut self, position: Vec2) {
+        self.checkpoint();
+        let shape = match self.active.filter(|active| *active < self.shapes.len()) {
+            Some(active) => active,
+            None => {
+                self.shapes.push(TerrainShape::default());
+                self.shapes.len() - 1
+            }
+        };
+        let vertices = &mut self.shapes[shape].vertices;
+        vertices.push(position);
+        self.active = Some(shape);
+        self.selected = Some((shape, vertices.len() - 1));
+        self.changed = true;
+    }
+
+    pub fn insert_vertex(&mut self, position: Vec2, radius: f32) -> bool {
+        match self.segment_at(position, radius) {
+            Some((shape, i)) => {
+                self.checkpoint();
+                self.shapes[shape].vertices.insert(i + 1, position);
+                self.selected = Some((shape, i + 1));
+                self.changed = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn move_selected(&mut self, position: Vec2) {
+        let selected = match self.selected {
+            Some(selected) => selected,
+            None => return,
+        };
+        if self
+            .vertex(selected)
+            .map_or(true, |vertex| vertex == position)
+        {
+            return;
+        }
+        if !self.drag_saved {
+            self.checkpoint();
+            self.drag_saved = true;
+        }
+        self.shapes[selected.0].vertices[selected.1] = position;
+        self.changed = true;
+    }
+
+    /// Deletes the selected vertex, shapes without vertices are removed.
+    pub fn delete_selected(&mut self) {
+        if let Some((shape, vertex)) = self.selected.take() {
+            if self.vertex((shape, vertex)).is_none() {
+                return;
+            }
+            self.checkpoint();
+            let vertices = &mut self.shapes[shape].vertices;
+            vertices.remove(vertex);
+            if vertices.is_empty() {
+                self.shapes.remove(shape);
+                self.active = None;
+            } else {
+                self.selected = Some((shape, vertex.min(vertices.len() - 1)));
+            }
+            self.changed = true;
+        }
+    }
+
+    pub fn toggle_closed(&mut self) {
+        if let Some(shape) = self.selected.map(|(shape, _)| shape).or(self.active) {
+            if shape < self.shapes.len() {
+                self.checkpoint();
+                self.shapes[shape].closed = !self.shapes[shape].closed;
+                self.changed = true;
+            }
+        }
+    }
+
+    pub fn set_surface(&mut self, surface: Option<SurfaceType>) {
+        if let Some(shape) = self.selected.map(|(shape, _)| shape).or(self.active) {
+            if shape < self.shapes.len() && self.shapes[shape].surface != surface {
+                self.checkpoint();
+                self.shapes[shape].surface = surface;
+                self.changed = true;
+            }
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(&self.shapes)?)
+    }
+
+    pub fn load(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let shapes = serde_json::from_str(&fs::read_to_string(path)?)?;
+        self.checkpoint();
+        self.set_shapes(shapes);
+        Ok(())
+    }
+}
+
+fn distance_to_segment(position: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let t = if ab.length_squared() > 0.0 {
+        ((position - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    position.distance(a + ab * t)
+}
@@ -1,21 +1,30 @@
 use bevy::prelude::*;
+#[cfg(feature = "dev")]
 use bevy_inspector_egui::Inspectable;
 
 use crate::game::components::robot::AttachmentPointId;
 
-#[derive(Default, Inspectable, Clone)]
+#[derive(Default, Clone)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct UiState {
     pub show_attachment_menu: Option<UiAttachmentMenu>,
     pub show_attachment_points: bool,
+    pub show_sensor_rays: bool,
+    pub show_pose_estimate: bool,
+    pub show_gas_heatmap: bool,
     pub manometers_inspected: usize,
+    pub hot_spots_inspected: usize,
+    pub report_status: String,
 }
 
-#[derive(Default, Inspectable, Clone)]
+#[derive(Default, Clone)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct UiAttachmentMenu {
     pub item_to_attach_to: UiAttachmentItem,
 }
 
-#[derive(Default, Inspectable, Clone)]
+#[derive(Default, Clone)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct UiAttachmentItem {
     pub entity: Option<Entity>,
     pub attachment_point_id: AttachmentPointId,
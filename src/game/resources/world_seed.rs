@@ -0,0 +1,71 @@
+use rand::{rngs::StdRng, SeedableRng};
+
+const SEED_ARG: &str = "--seed";
+const SEED_ENV: &str = "IDLE_INSPECTION_SEED";
+
+/// Seed and random number generator for everything that shapes the facility layout.
+pub struct WorldSeed {
+    pub seed: u64,
+    pub rng: StdRng,
+}
+
+impl Default for WorldSeed {
+    fn default() -> Self {
+        Self::new(Self::configured().unwrap_or_else(rand::random))
+    }
+}
+
+impl WorldSeed {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Reads the seed from `--seed <seed>` or `--seed=<seed>` on the command line, falling
+    /// back to the `IDLE_INSPECTION_SEED` environment variable.
+    pub fn configured() -> Option<u64> {
+        let args = std::env::args().collect::<Vec<_>>();
+        args.iter()
+            .enumerate()
+            .find_map(|(i, arg)| match arg.strip_prefix(SEED_ARG) {
+                Some("") => args.get(i + 1).cloned(),
+                Some(value) => value.strip_prefix('=').map(str::to_string),
+                None => None,
+            })
+            .or_else(|| std::env::var(SEED_ENV).ok())
+            .and_then(|seed| seed.trim().parse().ok())
+    }
+
+    /// Restarts the generator so the layout can be built again from the beginning.
+    pub fn reset(&mut self) {
+        self.rng = StdRng::seed_from_u64(self.seed);
+    }
+}
+
+/// Random numbers of a single system, kept in a `Local`. Each system draws from a stream of
+/// its own derived from the world seed, so the order systems run in does not change what
+/// the others draw.
+#[derive(Default)]
+pub struct SystemRng {
+    rng: Option<(u64, StdRng)>,
+}
+
+impl SystemRng {
+    /// The generator of `stream`, started over whenever the world seed changes.
+    pub fn get(&mut self, world_seed: &WorldSeed, stream: &str) -> &mut StdRng {
+        let seed = world_seed.seed;
+        if !matches!(self.rng, Some((current, _)) if current == seed) {
+            self.rng = Some((seed, StdRng::seed_from_u64(seed ^ stream_hash(stream))));
+        }
+        &mut self.rng.as_mut().unwrap().1
+    }
+}
+
+/// FNV-1a, stable across runs and platforms unlike the std hasher.
+fn stream_hash(stream: &str) -> u64 {
+    stream.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+use bevy_rapier2d::physics::PhysicsSystems;
+
+use crate::GameState;
+
+/// Phases of a game frame. All of them run in `CoreStage::Update` while in
+/// `GameState::Game`, each one after the previous has finished. Only `build_pipe_line`
+/// runs on a fixed step instead, and skips the ticks before the game started:
+///
+/// 1. `Input` reads keyboard and mouse and queues the player's robot commands.
+/// 2. `Attach` attaches waiting items and tags them with their robot, so everything later
+///    in the frame sees complete robots and `Changed<ParentEntity>`.
+/// 3. `Commands` lets the autopilots, doors and new lenses queue their robot commands.
+/// 4. `Execute` charges the batteries, then `handle_command` drains the whole queue.
+///    Commands queued by `Sensing` are executed in the next frame, every other command
+///    in the frame it was queued.
+/// 5. `Physics` adjusts forces, damping, traction and colliders, then rapier steps.
+/// 6. `Sensing` reads the stepped world: range and gas sensors, odometry, exploration.
+/// 7. `Inspection` simulates pressure, progresses inspections, compliance and alarms.
+/// 8. `Ui` draws the state of this frame and runs the egui panels.
+///
+/// Entities spawned or despawned with `Commands` appear at the end of the stage and are
+/// attached in the next frame. Within a phase, systems that write the same resource are
+/// ordered with `CommandSource` and `InspectionSystem`, so a frame runs the same way
+/// every time.
+#[derive(SystemLabel, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameSystem {
+    Input,
+    Attach,
+    Commands,
+    Execute,
+    Physics,
+    Sensing,
+    Inspection,
+    Ui,
+}
+
+impl GameSystem {
+    pub const ALL: [GameSystem; 8] = [
+        GameSystem::Input,
+        GameSystem::Attach,
+        GameSystem::Commands,
+        GameSystem::Execute,
+        GameSystem::Physics,
+        GameSystem::Sensing,
+        GameSystem::Inspection,
+        GameSystem::Ui,
+    ];
+
+    pub fn previous(self) -> Option<GameSystem> {
+        let index = Self::ALL.iter().position(|phase| *phase == self)?;
+        index.checked_sub(1).map(|previous| Self::ALL[previous])
+    }
+
+    /// The systems of this phase, while in game.
+    pub fn set(self) -> SystemSet {
+        let set = SystemSet::on_update(GameState::Game).label(self);
+        let set = match self.previous() {
+            Some(previous) => set.after(previous),
+            None => set,
+        };
+        match self {
+            GameSystem::Physics => set.before(PhysicsSystems::StepWorld),
+            GameSystem::Sensing => set.after(PhysicsSystems::StepWorld),
+            _ => set,
+        }
+    }
+}
+
+/// Systems that queue robot commands, in the order their commands are executed.
+#[derive(SystemLabel, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CommandSource {
+    Drive,
+    MoveJoint,
+    Zoom,
+    Lens,
+    Autopilot,
+    Doors,
+    RangeSensors,
+    GasSensors,
+}
+
+/// Order within `GameSystem::Inspection`.
+#[derive(SystemLabel, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InspectionSystem {
+    Faults,
+    Network,
+    Pressure,
+    Targets,
+    Quality,
+    Progress,
+    Complete,
+}
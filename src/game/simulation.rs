@@ -0,0 +1,56 @@
+use bevy::{asset::AssetPlugin, core::CoreSystem, prelude::*, transform::TransformPlugin};
+
+use crate::{
+    game::{
+        plugins::{
+            facility::FacilityPlugin, inspection::InspectionPlugin, item::ItemPlugin,
+            power::PowerPlugin, robot::RobotPlugin,
+        },
+        schedule::GameSystem,
+        systems::scenario,
+    },
+    GameState,
+};
+
+/// Everything that runs the facility without drawing it. Shared by the game and the
+/// headless `sim` runner, which add the `UiPlugin` or a scenario on top.
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_state(GameState::AssetLoading)
+            .add_plugin(ItemPlugin)
+            .add_plugin(RobotPlugin)
+            .add_plugin(PowerPlugin)
+            .add_plugin(InspectionPlugin)
+            .add_plugin(FacilityPlugin);
+    }
+}
+
+/// Runs a `Scenario` without a window on a simulated clock, add it with `MinimalPlugins`
+/// before the `SimulationPlugin`. Sprites are still created but never drawn.
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_plugin(AssetPlugin)
+            .add_plugin(TransformPlugin)
+            .add_asset::<Texture>()
+            .add_asset::<TextureAtlas>()
+            .add_asset::<ColorMaterial>()
+            .init_resource::<Input<KeyCode>>()
+            .add_system_to_stage(
+                CoreStage::First,
+                scenario::advance_clock.system().after(CoreSystem::Time),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::Game).with_system(scenario::spawn_scenario.system()),
+            )
+            .add_system_set(
+                GameSystem::Sensing
+                    .set()
+                    .with_system(scenario::track_metrics.system()),
+            )
+            .add_system_set(GameSystem::Ui.set().with_system(scenario::finish.system()));
+    }
+}
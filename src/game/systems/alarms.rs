@@ -0,0 +1,152 @@
+use bevy::{log, prelude::*};
+use rand::{rngs::StdRng, seq::IteratorRandom, Rng};
+
+use crate::game::{
+    components::robot::*,
+    resources::{
+        incident_log::{
+            AlarmEvent, AlarmResponse, FaultSchedule, Incident, IncidentKind, IncidentLog,
+        },
+        world_seed::{SystemRng, WorldSeed},
+    },
+};
+
+pub fn inject_faults(
+    mut commands: Commands,
+    time: Res<Time>,
+    world_seed: Res<WorldSeed>,
+    mut rng: Local<SystemRng>,
+    mut schedule: ResMut<FaultSchedule>,
+    manometers: Query<(Entity, &Manometer), Without<Fault>>,
+) {
+    let rng = rng.get(&world_seed, "faults");
+    let now = time.seconds_since_startup();
+    let deadline = now + schedule.deadline as f64;
+    let mut new_fault =
+        |rng: &mut StdRng, entity: Entity, manometer: &Manometer, kind: FaultKind| {
+            commands
+                .entity(entity)
+                .insert(roll_fault(rng, manometer, kind, now, deadline));
+        };
+
+    let due = schedule
+        .scripted
+        .iter()
+        .filter(|scripted| scripted.at <= now)
+        .map(|scripted| scripted.kind)
+        .collect::<Vec<_>>();
+    schedule.scripted.retain(|scripted| scripted.at > now);
+    let mut picked = manometers.iter().choose_multiple(rng, due.len());
+    for kind in due {
+        if let Some((entity, manometer)) = picked.pop() {
+            new_fault(rng, entity, manometer, kind);
+        }
+    }
+
+    let chance = (schedule.rate / 60.0 * time.delta_seconds()) as f64;
+    for (entity, manometer) in manometers.iter() {
+        if rng.gen_bool(chance.clamp(0.0, 1.0)) {
+            let kind = if rng.gen() {
+                FaultKind::Drift
+            } else {
+                FaultKind::Spike
+            };
+            new_fault(rng, entity, manometer, kind);
+        }
+    }
+}
+
+/// A fault that pushes the pressure well past one end of the allowed band.
+pub fn roll_fault(
+    rng: &mut StdRng,
+    manometer: &Manometer,
+    kind: FaultKind,
+    started: f64,
+    deadline: f64,
+) -> Fault {
+    let band = manometer.allowed.1 - manometer.allowed.0;
+    let sign = if rng.gen::<bool>() { 1.0 } else { -1.0 };
+    Fault {
+        kind,
+        offset: sign * band * rng.gen_range(0.8..1.5),
+        started,
+        deadline,
+    }
+}
+
+pub fn expire_faults(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut incident_log: ResMut<IncidentLog>,
+    faults: Query<(Entity, &Fault, &Manometer, &ItemSeed)>,
+) {
+    let now = time.seconds_since_startup();
+    faults.for_each(|(entity, fault, manometer, seed)| {
+        if now >= fault.deadline {
+            incident_log.record(Incident {
+                kind: IncidentKind::Missed(fault.kind),
+                manometer: seed.0,
+                value: None,
+                unit: manometer.unit,
+                timestamp: now,
+            });
+            commands.entity(entity).remove::<Fault>();
+        }
+    });
+}
+
+pub fn handle_alarms(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut alarms: EventReader<AlarmEvent>,
+    mut incident_log: ResMut<IncidentLog>,
+    faults: Query<&Fault>,
+    mut manometers: Query<&mut Manometer>,
+    seeds: Query<&ItemSeed>,
+) {
+    for alarm in alarms.iter() {
+        let seed = seeds.get(alarm.manometer).map_or(0, |seed| seed.0);
+        let kind = match faults.get(alarm.manometer) {
+            Ok(fault) => {
+                commands.entity(alarm.manometer).remove::<Fault>();
+                IncidentKind::Detected(fault.kind)
+            }
+            Err(..) => IncidentKind::FalseAlarm,
+        };
+        log::info!(
+            "alarm: manometer {} read {:.2} {} outside {:?} ({:?})",
+            seed,
+            alarm.value,
+            alarm.unit,
+            alarm.allowed,
+            kind
+        );
+        incident_log.record(Incident {
+            kind,
+            manometer: seed,
+            value: Some(alarm.value),
+            unit: alarm.unit,
+            timestamp: time.seconds_since_startup(),
+        });
+
+        match incident_log.response {
+            AlarmResponse::None => {}
+            AlarmResponse::Reinspect => {
+                if let Ok(mut manometer) = manometers.get_mut(alarm.manometer) {
+                    manometer.last_inspected = None;
+                }
+            }
+            AlarmResponse::MarkForRepair => {
+                if let Some(pipe) = alarm.pipe {
+                    commands.entity(pipe).insert(NeedsRepair);
+                }
+            }
+        }
+    }
+}
+
+pub fn highlight_repairs(pipes: Query<&mut TextureAtlasSprite, Added<NeedsRepair>>) {
+    pipes.for_each_mut(|mut sprite| {
+        sprite.color = Color::rgb(1.0, 0.45, 0.35);
+    });
+}
@@ -5,12 +5,12 @@ use bevy::{log, prelude::*};
 use bevy_rapier2d::prelude::*;
 
 use crate::{
-    consts::PHYSICS_SCALE,
+    consts::{AMBIENT_TEMPERATURE, PHYSICS_SCALE},
     game::{
         components::{animation::AnimationDirection, robot::*},
         resources::sprite_asset::SpriteAsset,
     },
-    utils::map_range,
+    utils::{false_color, map_range},
 };
 
 pub fn motors(
@@ -87,11 +87,33 @@ pub fn battery(
 }
 
 pub fn manometer(
+    time: Res<Time>,
     manometer_sprite_query: Query<(&mut TextureAtlasSprite, &SpriteAsset, &Manometer)>,
 ) {
+    let now = time.seconds_since_startup();
     manometer_sprite_query.for_each_mut(|(mut texture, sprite, manometer)| {
         let max_frames = sprite.frames as f32;
         let frame = map_range(&(0.0..100.0), &(0.0..max_frames), manometer.progress);
         texture.index = (frame as u32).min(sprite.frames as u32 - 1);
+        texture.color = if manometer.occluded {
+            Color::rgba(0.5, 0.5, 0.5, 0.6)
+        } else {
+            manometer.status(now).color()
+        };
+    });
+}
+
+pub fn hot_spot(query: Query<(&mut TextureAtlasSprite, &HotSpot)>) {
+    query.for_each_mut(|(mut texture, hot_spot)| {
+        texture.color = match hot_spot.reading {
+            _ if hot_spot.occluded => Color::rgba(0.5, 0.5, 0.5, 0.6),
+            // the scale runs from ambient to a bit past the allowed maximum
+            Some(value) => false_color(map_range(
+                &(AMBIENT_TEMPERATURE..hot_spot.max_temperature * 1.25),
+                &(0.0..1.0),
+                value,
+            )),
+            None => Color::rgba(1.0, 1.0, 1.0, 0.5),
+        };
     });
 }
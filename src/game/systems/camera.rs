@@ -3,6 +3,8 @@ use bevy_egui::EguiContext;
 use bevy_interact_2d::{Group, InteractionSource};
 use bevy::render::camera::CameraProjection;
 
+use crate::game::resources::terrain_collider::TerrainEditor;
+
 pub fn setup(mut commands: Commands) {
     commands
         .spawn_bundle(OrthographicCameraBundle::new_2d())
@@ -17,10 +19,14 @@ pub fn pan(
     mouse_button_input: Res<Input<MouseButton>>,
     mut query: Query<&mut Transform, With<Camera>>,
     egui_ctx: Res<EguiContext>,
+    terrain_editor: Res<TerrainEditor>,
 ) {
     if egui_ctx.ctx().wants_pointer_input() || egui_ctx.ctx().is_pointer_over_area() {
         return;
     }
+    if terrain_editor.dragging {
+        return;
+    }
     if mouse_button_input.pressed(MouseButton::Left) {
         let mouse_delta = if let Some(mouse) = mouse_motion_events.iter().next() {
             mouse.delta
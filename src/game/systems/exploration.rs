@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    consts::PHYSICS_SCALE,
+    game::{
+        components::{exploration::*, robot::*},
+        resources::exploration_map::ExplorationMap,
+    },
+};
+
+pub fn spawn_fog(
+    mut commands: Commands,
+    exploration_map: Res<ExplorationMap>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let material = materials.add(Color::rgba(0.0, 0.0, 0.0, 0.6).into());
+    for index in 0..exploration_map.explored.len() {
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite::new(Vec2::splat(exploration_map.cell_size)),
+                material: material.clone(),
+                transform: Transform::from_translation(
+                    exploration_map.cell_center(index).extend(500.0),
+                ),
+                ..Default::default()
+            })
+            .insert(FogTile { index });
+    }
+}
+
+pub fn reveal_by_cameras(
+    mut exploration_map: ResMut<ExplorationMap>,
+    lenses: Query<(&ColliderShape, &ColliderPosition, &ParentEntity), With<CameraLens>>,
+) {
+    lenses.for_each(|(shape, collider_pos, parent_entity)| {
+        if *parent_entity == ParentEntity::WaitForAttach {
+            return;
+        }
+        if let Some(cuboid) = shape.as_cuboid() {
+            let cells = exploration_map.rect_cells(
+                Vec2::new(collider_pos.0.translation.x, collider_pos.0.translation.y)
+                    * PHYSICS_SCALE,
+                Vec2::new(cuboid.half_extents.x, cuboid.half_extents.y) * PHYSICS_SCALE,
+                collider_pos.0.rotation.angle(),
+            );
+            // only a mutable access marks the map as changed and redraws the fog
+            if exploration_map.any_unexplored(&cells) {
+                exploration_map.reveal(&cells);
+            }
+        }
+    });
+}
+
+pub fn reveal_by_range_sensors(
+    mut exploration_map: ResMut<ExplorationMap>,
+    sensors: Query<(&RangeSensor, &RigidBodyPosition)>,
+) {
+    sensors.for_each(|(sensor, rb_pos)| {
+        let start = Vec2::new(
+            rb_pos.position.translation.x,
+            rb_pos.position.translation.y,
+        ) * PHYSICS_SCALE;
+        sensor
+            .readings
+            .iter()
+            .enumerate()
+            .for_each(|(ray, distance)| {
+                let dir = rb_pos.position.rotation
+                    * Rotation::from_angle(sensor.ray_angle(ray))
+                    * Vector::y();
+                let cells =
+                    exploration_map.line_cells(start, start + Vec2::new(dir.x, dir.y) * *distance);
+                if exploration_map.any_unexplored(&cells) {
+                    exploration_map.reveal(&cells);
+                }
+            });
+    });
+}
+
+pub fn update_fog(
+    exploration_map: Res<ExplorationMap>,
+    tiles: Query<(&FogTile, &mut Visible)>,
+) {
+    if !exploration_map.is_changed() {
+        return;
+    }
+    tiles.for_each_mut(|(tile, mut visible)| {
+        let hidden = !exploration_map.explored[tile.index];
+        if visible.is_visible != hidden {
+            visible.is_visible = hidden;
+        }
+    });
+}
+
+pub fn discover_items(
+    mut commands: Commands,
+    exploration_map: Res<ExplorationMap>,
+    items: Query<(Entity, &RigidBodyPosition, &ParentEntity, &mut Visible), With<Undiscovered>>,
+) {
+    items.for_each_mut(|(entity, rb_pos, parent_entity, mut visible)| {
+        if *parent_entity == ParentEntity::WaitForAttach {
+            return;
+        }
+        let position = Vec2::new(
+            rb_pos.position.translation.x,
+            rb_pos.position.translation.y,
+        ) * PHYSICS_SCALE;
+        if exploration_map.is_explored(position) {
+            visible.is_visible = true;
+            commands.entity(entity).remove::<Undiscovered>();
+        } else {
+            visible.is_visible = false;
+        }
+    });
+}
@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    game::{
+        components::{exploration::GasTile, robot::*},
+        resources::{gas_field::GasField, robot_commands::*, ui::UiState},
+    },
+    utils::world_position,
+};
+
+// concentration shown at full heatmap intensity
+const HEATMAP_SCALE: f32 = 2.0;
+
+pub fn emit_leaks(
+    time: Res<Time>,
+    mut gas_field: ResMut<GasField>,
+    leaks: Query<(&GasLeak, &RigidBodyPosition, &ParentEntity)>,
+) {
+    leaks.for_each(|(leak, rb_pos, parent_entity)| {
+        if *parent_entity != ParentEntity::WaitForAttach {
+            gas_field.emit(world_position(rb_pos), leak.rate * time.delta_seconds());
+        }
+    });
+}
+
+pub fn diffuse_gas(time: Res<Time>, mut gas_field: ResMut<GasField>) {
+    gas_field.step(time.delta_seconds());
+}
+
+pub fn gas_sensors(
+    time: Res<Time>,
+    mut gas_field: ResMut<GasField>,
+    mut sensors: Query<(
+        Entity,
+        &mut GasSensor,
+        &mut Powered,
+        &RigidBodyPosition,
+        &ParentEntity,
+    )>,
+    mut robot_commands: ResMut<RobotCommands>,
+) {
+    for (entity, mut sensor, mut powered, rb_pos, parent_entity) in sensors.iter_mut() {
+        robot_commands.send(RobotCommand {
+            robot_entity: *parent_entity,
+            command: RobotCommandType::Sense { entity },
+            power_consumption: sensor.power_consumption,
+        });
+        if !powered.0 {
+            continue;
+        }
+        powered.0 = false;
+
+        let position = world_position(rb_pos);
+        sensor.respond(gas_field.concentration_at(position), time.delta_seconds());
+        gas_field.sample(position);
+    }
+}
+
+pub fn spawn_heatmap(
+    mut commands: Commands,
+    gas_field: Res<GasField>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for index in 0..gas_field.concentration.len() {
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite::new(Vec2::splat(gas_field.cell_size)),
+                material: materials.add(Color::NONE.into()),
+                transform: Transform::from_translation(gas_field.cell_center(index).extend(400.0)),
+                visible: Visible {
+                    is_visible: false,
+                    is_transparent: true,
+                },
+                ..Default::default()
+            })
+            .insert(GasTile { index });
+    }
+}
+
+pub fn update_heatmap(
+    gas_field: Res<GasField>,
+    ui_state: Res<UiState>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    tiles: Query<(&GasTile, &Handle<ColorMaterial>, &mut Visible)>,
+) {
+    tiles.for_each_mut(|(tile, material, mut visible)| {
+        let shown = ui_state.show_gas_heatmap && gas_field.sampled[tile.index];
+        if visible.is_visible != shown {
+            visible.is_visible = shown;
+        }
+        if !shown {
+            return;
+        }
+        let intensity = (gas_field.concentration[tile.index] / HEATMAP_SCALE).clamp(0.0, 1.0);
+        if let Some(material) = materials.get_mut(material) {
+            material.color = Color::rgba(1.0, 1.0 - intensity, 0.0, 0.1 + 0.5 * intensity);
+        }
+    });
+}
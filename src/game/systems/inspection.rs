@@ -2,129 +2,734 @@ use crate::game::{
     builders::item::{ItemBuilder, ItemSpawner},
     components::robot::*,
     resources::{
+        compliance::FacilityCompliance,
+        incident_log::AlarmEvent,
+        inspection_log::{InspectionLog, ManometerReading, ThermalReading},
         item_collection::{ItemCollection, LoadedItem},
         item_information::InformationCollection,
-        pipe_line::PipeLine,
+        pipe_line::{PipeLine, PipeLineConfig, PipeSegment},
+        save_game::unix_time,
         ui::UiState,
+        world_seed::{SystemRng, WorldSeed},
     },
+    systems::movement::robot_pose,
+    types::{EnvironmentItemType, ItemType, Spectrum},
+};
+use crate::{consts::PHYSICS_SCALE, utils::world_position, GameState};
+use bevy::{log, prelude::*, utils::HashMap};
+use bevy_rapier2d::{
+    physics::{
+        IntoEntity, JointHandleComponent, QueryPipelineColliderComponentsQuery,
+        QueryPipelineColliderComponentsSet,
+    },
+    prelude::*,
 };
-use bevy::prelude::*;
-use bevy_rapier2d::{physics::IntoEntity, prelude::IntersectionEvent};
 use rand::prelude::*;
 use rand::seq::SliceRandom;
-// TODO: advance with timer
+
+const INSPECTION_RATE: f32 = 10.0;
+pub const MIN_QUALITY: f32 = 0.2;
+const BLUR_SPEED: f32 = 30.0;
+const READING_ERROR: f32 = 0.1;
+// thermal cameras are specified as accurate to 2 °C or 2 % of the reading, whichever is larger
+const THERMAL_ACCURACY: (f32, f32) = (2.0, 0.02);
+
 pub fn inspect_manometer(
     mut query_manometer: Query<&mut Manometer>,
-    mut query_camera_lenses: Query<&CameraLens>,
+    mut query_hot_spots: Query<&mut HotSpot>,
+    mut query_camera_lenses: Query<&mut LensTargets, With<CameraLens>>,
     mut intersection_events: EventReader<IntersectionEvent>,
 ) {
     for intersection_event in intersection_events.iter() {
+        let (a, b) = (
+            intersection_event.collider1.entity(),
+            intersection_event.collider2.entity(),
+        );
+        let (lens, manometer) = if query_camera_lenses.get_mut(a).is_ok() {
+            (a, b)
+        } else {
+            (b, a)
+        };
         match (
-            query_camera_lenses.get_mut(intersection_event.collider1.entity()),
-            query_manometer.get_mut(intersection_event.collider2.entity()),
+            query_camera_lenses.get_mut(lens),
+            query_manometer.get_mut(manometer),
         ) {
-            (Ok(..), Ok(ref mut manometer)) => {
-                manometer.inspections += if intersection_event.intersecting {
-                    1.
+            (Ok(mut targets), Ok(mut manometer_component)) => {
+                if intersection_event.intersecting {
+                    manometer_component.inspections += 1.;
+                    targets.0.push(manometer);
                 } else {
-                    -1.
-                };
+                    manometer_component.inspections -= 1.;
+                    targets.0.retain(|target| *target != manometer);
+                }
+            }
+            (Ok(mut targets), Err(..)) if query_hot_spots.get_mut(manometer).is_ok() => {
+                if intersection_event.intersecting {
+                    targets.0.push(manometer);
+                } else {
+                    targets.0.retain(|target| *target != manometer);
+                }
             }
             _ => {}
         }
     }
 }
 
+pub fn inspection_quality(
+    query_pipeline: Res<QueryPipeline>,
+    collider_query: QueryPipelineColliderComponentsQuery,
+    colliders: Query<(&ParentEntity, &ColliderType)>,
+    mounts: Query<&JointHandleComponent>,
+    cameras: Query<(
+        &ImageQuality,
+        &AttachmentMap<Attachment>,
+        &RigidBodyPosition,
+        &RigidBodyVelocity,
+        &ParentEntity,
+    )>,
+    mut lenses: Query<(&CameraLens, &LensTargets, &mut InspectionQuality)>,
+    positions: Query<&RigidBodyPosition>,
+    mut manometers: Query<&mut Manometer>,
+    mut hot_spots: Query<&mut HotSpot>,
+) {
+    let collider_set = QueryPipelineColliderComponentsSet(&collider_query);
+    // per target: whether at least one camera has a clear line of sight
+    let mut line_of_sight: HashMap<Entity, bool> = HashMap::default();
+
+    cameras.for_each(|(image_quality, attachments, rb_pos, rb_vel, parent)| {
+        let lens = attachments
+            .0
+            .get(&AttachmentPointId::CameraLens)
+            .and_then(|attachment| attachment.attached)
+            .map(|(lens, _)| lens);
+        if let Some(Ok((camera_lens, targets, mut inspection_quality))) =
+            lens.map(|lens| lenses.get_mut(lens))
+        {
+            let camera_pos = world_position(rb_pos);
+            let forward = rb_pos.position.rotation * Vector::y();
+            let forward = Vec2::new(forward.x, forward.y);
+            let linear_speed = rb_vel.linvel.magnitude() * PHYSICS_SCALE;
+
+            *inspection_quality = InspectionQuality::default();
+            targets.0.iter().for_each(|target| {
+                // dials need visible light, hot spots only show up in thermal images
+                let thermal = hot_spots.get_mut(*target).is_ok();
+                if thermal != (image_quality.spectrum == Spectrum::Thermal) {
+                    return;
+                }
+                if let Ok(target_pos) = positions.get(*target) {
+                    let to_target = world_position(target_pos) - camera_pos;
+                    let distance = to_target.length().max(1.0);
+
+                    let visible = has_line_of_sight(
+                        &query_pipeline,
+                        &collider_set,
+                        &colliders,
+                        rb_pos,
+                        (*target, target_pos),
+                        mounts.get(*target).map(|joint| joint.entity1()).ok(),
+                        parent,
+                    );
+                    let any_visible = line_of_sight.entry(*target).or_insert(false);
+                    *any_visible |= visible;
+                    if !visible {
+                        inspection_quality.blocked += 1;
+                        return;
+                    }
+
+                    let focal_length = camera_lens.focal_length.max(1.0);
+                    let focus =
+                        (1.0 - (distance - focal_length).abs() / focal_length).clamp(0.0, 1.0);
+                    let detail = ((image_quality.width * image_quality.height).sqrt()
+                        * focal_length
+                        / distance)
+                        .min(1.0)
+                        * (1.0 - image_quality.noise).clamp(0.0, 1.0);
+                    let blur =
+                        1.0 / (1.0 + (linear_speed + rb_vel.angvel.abs() * distance) / BLUR_SPEED);
+                    let aim = (forward.dot(to_target) / distance).max(0.0);
+                    // a dial is mounted on the +x side of its gauge and reads badly from aside
+                    let angle = if thermal {
+                        aim
+                    } else {
+                        let facing = target_pos.position.rotation * Vector::x();
+                        let facing = Vec2::new(facing.x, facing.y);
+                        aim * (-facing.dot(to_target) / distance).max(0.0)
+                    };
+                    let quality = focus * detail * blur * angle;
+
+                    if quality >= inspection_quality.quality {
+                        *inspection_quality = InspectionQuality {
+                            target: Some(*target),
+                            quality,
+                            focus,
+                            detail,
+                            blur,
+                            angle,
+                            blocked: inspection_quality.blocked,
+                        };
+                    }
+                }
+            });
+        }
+    });
+
+    manometers.for_each_mut(|mut manometer| {
+        if manometer.occluded {
+            manometer.occluded = false;
+        }
+    });
+    hot_spots.for_each_mut(|mut hot_spot| {
+        if hot_spot.occluded {
+            hot_spot.occluded = false;
+        }
+    });
+    line_of_sight.iter().for_each(|(target, visible)| {
+        if let Ok(mut manometer) = manometers.get_mut(*target) {
+            manometer.occluded = !*visible;
+        } else if let Ok(mut hot_spot) = hot_spots.get_mut(*target) {
+            hot_spot.occluded = !*visible;
+        }
+    });
+}
+
+/// Where a robot places a target it looks at, by its own idea of where it stands.
+fn believed_position(
+    target: Vec2,
+    rb_pos: &RigidBodyPosition,
+    estimate: Option<&PoseEstimate>,
+) -> Vec2 {
+    let (position, heading) = robot_pose(rb_pos, estimate);
+    let offset = target - world_position(rb_pos);
+    position + Mat2::from_angle(heading - rb_pos.position.rotation.angle()) * offset
+}
+
+/// Casts a ray from the camera to the target. The robots own parts and the pipe the
+/// gauge is mounted on never block the view.
+fn has_line_of_sight(
+    query_pipeline: &QueryPipeline,
+    collider_set: &QueryPipelineColliderComponentsSet,
+    colliders: &Query<(&ParentEntity, &ColliderType)>,
+    camera_pos: &RigidBodyPosition,
+    (target, target_pos): (Entity, &RigidBodyPosition),
+    mount: Option<Entity>,
+    robot: &ParentEntity,
+) -> bool {
+    let origin = camera_pos.position.translation.vector;
+    let to_target = target_pos.position.translation.vector - origin;
+    let distance = to_target.magnitude();
+    if distance <= f32::EPSILON {
+        return true;
+    }
+    let filter = |handle: ColliderHandle| {
+        let entity = handle.entity();
+        Some(entity) != mount
+            && match colliders.get(entity) {
+                Ok((other, collider_type)) => {
+                    other != robot && *collider_type == ColliderType::Solid
+                }
+                Err(..) => true,
+            }
+    };
+    query_pipeline
+        .cast_ray(
+            collider_set,
+            &Ray::new(origin.into(), to_target / distance),
+            distance,
+            true,
+            InteractionGroups::all(),
+            Some(&filter),
+        )
+        .map(|(handle, _)| handle.entity() == target)
+        .unwrap_or(true)
+}
+
 pub fn update_manometer_progress(
     time: Res<Time>,
-    query_manometer: Query<(&mut Manometer, &mut Timer)>,
+    lenses: Query<(Entity, &InspectionQuality)>,
+    mut query_manometer: Query<(&mut Manometer, &mut InspectionProgress)>,
 ) {
-    query_manometer.for_each_mut(|(ref mut manometer, ref mut timer)| {
-        if manometer.inspections > 0.0 {
-            timer.tick(time.delta());
-            if timer.finished() {
-                manometer.progress += manometer.inspections as f32;
+    let now = time.seconds_since_startup();
+    lenses.for_each(|(lens, inspection_quality)| {
+        if inspection_quality.quality < MIN_QUALITY {
+            return;
+        }
+        if let Some(Ok((mut manometer, mut inspection_progress))) = inspection_quality
+            .target
+            .map(|target| query_manometer.get_mut(target))
+        {
+            if !manometer.needs_inspection(now) {
+                return;
             }
+            let gained = inspection_quality.quality * INSPECTION_RATE * time.delta_seconds();
+            manometer.quality = (manometer.quality * manometer.progress
+                + inspection_quality.quality * gained)
+                / (manometer.progress + gained);
+            manometer.progress += gained;
+
+            if inspection_progress.started.is_none() {
+                inspection_progress.started = Some(now);
+            }
+            inspection_progress.lens = Some(lens);
         }
     });
 }
 
 pub fn complete_manometer_progress(
-    mut commands: Commands,
+    time: Res<Time>,
+    world_seed: Res<WorldSeed>,
+    mut rng: Local<SystemRng>,
+    mut ui_state: ResMut<UiState>,
+    mut inspection_log: ResMut<InspectionLog>,
+    query_manometer: Query<
+        (
+            Entity,
+            &ItemSeed,
+            &mut Manometer,
+            &mut InspectionProgress,
+            &Transform,
+            Option<&JointHandleComponent>,
+        ),
+        Changed<Manometer>,
+    >,
+    lenses: Query<(&ItemName, &ParentEntity, &JointHandleComponent), With<CameraLens>>,
+    names: Query<&ItemName>,
+    seeds: Query<&ItemSeed>,
+    robots: Query<(&RigidBodyPosition, Option<&PoseEstimate>)>,
+    mut alarms: EventWriter<AlarmEvent>,
+) {
+    let rng = rng.get(&world_seed, "manometer readings");
+    query_manometer.for_each_mut(
+        |(entity, seed, mut manometer, mut inspection_progress, transform, mount)| {
+            if manometer.progress >= 100.0 {
+                // a poorly taken reading is off by up to READING_ERROR of the dial range
+                let error = (1.0 - manometer.quality)
+                    * READING_ERROR
+                    * (manometer.range.1 - manometer.range.0)
+                    * rng.gen_range(-1.0..=1.0);
+                let value = manometer.pressure + error;
+                manometer.reading = Some(value);
+
+                let lens = inspection_progress
+                    .lens
+                    .and_then(|lens| lenses.get(lens).ok());
+                let robot = match lens {
+                    Some((_, ParentEntity::Robot(Some(robot)), _)) => Some(*robot),
+                    _ => None,
+                };
+                let target = transform.translation.truncate();
+                let position = match robot.and_then(|robot| robots.get(robot).ok()) {
+                    Some((rb_pos, estimate)) => believed_position(target, rb_pos, estimate),
+                    None => target,
+                };
+                let camera = lens
+                    .and_then(|(_, _, joint)| names.get(joint.entity1()).ok())
+                    .map(|name| name.0.clone())
+                    .unwrap_or_default();
+                let timestamp = time.seconds_since_startup();
+                let in_band = manometer.in_band(value);
+                if !in_band {
+                    alarms.send(AlarmEvent {
+                        manometer: entity,
+                        pipe: mount.map(|joint| joint.entity1()),
+                        value,
+                        unit: manometer.unit,
+                        allowed: manometer.allowed,
+                    });
+                }
+                inspection_log.record(ManometerReading {
+                    manometer: seed.0,
+                    x: position.x,
+                    y: position.y,
+                    pipe: mount
+                        .and_then(|joint| seeds.get(joint.entity1()).ok())
+                        .map(|seed| seed.0),
+                    robot: robot
+                        .and_then(|robot| seeds.get(robot).ok())
+                        .map(|seed| seed.0),
+                    camera,
+                    lens: lens.map(|(name, ..)| name.0.clone()).unwrap_or_default(),
+                    value,
+                    unit: manometer.unit,
+                    in_band,
+                    quality: manometer.quality,
+                    timestamp,
+                    recorded_at: unix_time(),
+                    duration: inspection_progress
+                        .started
+                        .map(|started| (timestamp - started) as f32)
+                        .unwrap_or_default(),
+                });
+                // the gauge stays in the facility and becomes due again after its interval
+                manometer.last_inspected = Some(timestamp);
+                manometer.progress = 0.0;
+                manometer.quality = 0.0;
+                *inspection_progress = InspectionProgress::default();
+                ui_state.manometers_inspected += 1;
+            }
+        },
+    );
+}
+
+pub fn update_hot_spot_progress(
+    time: Res<Time>,
+    lenses: Query<&InspectionQuality>,
+    mut hot_spots: Query<&mut HotSpot>,
+) {
+    let now = time.seconds_since_startup();
+    lenses.for_each(|inspection_quality| {
+        if inspection_quality.quality < MIN_QUALITY {
+            return;
+        }
+        if let Some(Ok(mut hot_spot)) = inspection_quality
+            .target
+            .map(|target| hot_spots.get_mut(target))
+        {
+            if !hot_spot.needs_inspection(now) {
+                return;
+            }
+            let gained = inspection_quality.quality * INSPECTION_RATE * time.delta_seconds();
+            hot_spot.quality = (hot_spot.quality * hot_spot.progress
+                + inspection_quality.quality * gained)
+                / (hot_spot.progress + gained);
+            hot_spot.progress += gained;
+        }
+    });
+}
+
+pub fn complete_hot_spot_progress(
+    time: Res<Time>,
+    world_seed: Res<WorldSeed>,
+    mut rng: Local<SystemRng>,
     mut ui_state: ResMut<UiState>,
-    query_manometer: Query<(Entity, &Manometer), Changed<Manometer>>,
+    mut inspection_log: ResMut<InspectionLog>,
+    hot_spots: Query<(&ItemSeed, &mut HotSpot, &Transform), Changed<HotSpot>>,
 ) {
-    query_manometer.for_each_mut(|(entity, ref mut manometer)| {
-        if manometer.progress >= 100.0 {
-            commands.entity(entity).despawn_recursive();
-            ui_state.manometers_inspected += 1;
+    let rng = rng.get(&world_seed, "thermal readings");
+    hot_spots.for_each_mut(|(seed, mut hot_spot, transform)| {
+        if hot_spot.progress >= 100.0 {
+            let accuracy = THERMAL_ACCURACY
+                .0
+                .max(THERMAL_ACCURACY.1 * hot_spot.temperature.abs());
+            let error =
+                accuracy * (1.0 + 4.0 * (1.0 - hot_spot.quality)) * rng.gen_range(-1.0..=1.0);
+            let value = hot_spot.temperature + error;
+            let timestamp = time.seconds_since_startup();
+            hot_spot.reading = Some(value);
+            inspection_log.record_thermal(ThermalReading {
+                hot_spot: seed.0,
+                x: transform.translation.x,
+                y: transform.translation.y,
+                value,
+                overheated: hot_spot.overheated(value),
+                quality: hot_spot.quality,
+                timestamp,
+                recorded_at: unix_time(),
+            });
+            hot_spot.last_inspected = Some(timestamp);
+            hot_spot.progress = 0.0;
+            hot_spot.quality = 0.0;
+            ui_state.hot_spots_inspected += 1;
         }
     });
 }
 
+pub fn update_compliance(
+    time: Res<Time>,
+    mut compliance: ResMut<FacilityCompliance>,
+    manometers: Query<&Manometer>,
+    hot_spots: Query<&HotSpot>,
+) {
+    let now = time.seconds_since_startup();
+    let mut current = FacilityCompliance::default();
+    manometers
+        .iter()
+        .map(|manometer| manometer.status(now))
+        .chain(hot_spots.iter().map(|hot_spot| hot_spot.status(now)))
+        .for_each(|status| {
+            current.targets += 1;
+            match status {
+                InspectionStatus::Uninspected => current.uninspected += 1,
+                InspectionStatus::Ok => {}
+                InspectionStatus::Due => current.due += 1,
+                InspectionStatus::Overdue => current.overdue += 1,
+            }
+        });
+    *compliance = current;
+}
+
+pub fn attach_manometer<'a, 'w>(
+    pipe: &'a mut ItemBuilder<'w>,
+    item_collection: &ItemCollection,
+) -> &'a mut ItemBuilder<'w> {
+    pipe.attach_then(
+        &item_collection.simple_manometer_icon,
+        AttachmentPointId::Manometer,
+        |icon| {
+            icon.attach_then(
+                &item_collection.simple_manometer_background,
+                AttachmentPointId::ManometerBackground,
+                |background| {
+                    background.attach_then(
+                        &item_collection.simple_manometer_frame,
+                        AttachmentPointId::ManometerFrame,
+                        |frame| {
+                            frame.attach_then(
+                                &item_collection.medium_manometer_markings,
+                                AttachmentPointId::ManometerMarkings,
+                                |markings| {
+                                    markings.attach(
+                                        &item_collection.fancy_manometer_pointer,
+                                        AttachmentPointId::ManometerPointer,
+                                    )
+                                },
+                            )
+                        },
+                    )
+                },
+            )
+        },
+    )
+}
+
+/// Runs on a fixed step instead of the state criteria, so it waits for the game itself.
 pub fn build_pipe_line(
     time: Res<Time>,
+    state: Res<State<GameState>>,
     mut commands: Commands,
     mut pipe_line: ResMut<PipeLine>,
+    mut world_seed: ResMut<WorldSeed>,
     information_collection: Res<InformationCollection>,
-    item_collection: Res<ItemCollection>,
+    item_collection: Option<Res<ItemCollection>>,
     items: Res<Assets<LoadedItem>>,
+    query_pipeline: Res<QueryPipeline>,
+    collider_query: QueryPipelineColliderComponentsQuery,
+    colliders: Query<(&ColliderType, &ItemType, Option<&ParentEntity>)>,
+    pipes: Query<(
+        &RigidBodyPosition,
+        &AttachmentMap<Attachment>,
+        &ParentEntity,
+    )>,
 ) {
-    let spawner = ItemSpawner::new(&items, &information_collection, &item_collection);
+    let item_collection = match item_collection {
+        Some(item_collection) if state.current() == &GameState::Game => item_collection,
+        _ => return,
+    };
+    let collider_set = QueryPipelineColliderComponentsSet(&collider_query);
 
     // pipe_line.timer.tick(time.delta());
 
     // if pipe_line.timer.finished() && rand::random() {
-    if let Some((i, &(parent, aid))) = pipe_line
+    if pipe_line.finished {
+        return;
+    }
+    if pipe_line.root.is_none() {
+        let rng = &mut world_seed.rng;
+        let transform = Transform::from_translation(Vec3::new(200.0, 40.0, 90.0));
+        let spawner =
+            ItemSpawner::new(&items, &information_collection, &item_collection, rng.gen());
+        let root = spawner
+            .item(&item_collection.gray_pipe)
+            .transform(transform)
+            .seed(rng.gen())
+            .build(&mut commands);
+        commands.entity(root).insert(PipeSource { head: 1.0 });
+        pipe_line.start(root, AttachmentPointId::Next);
+        return;
+    }
+    pipe_line.current.retain(|(parent, aid)| {
+        pipes
+            .get(*parent)
+            .map_or(false, |(_, attachments, _)| attachments.0.contains_key(aid))
+    });
+    if !pipe_line.has_budget() || pipe_line.current.is_empty() {
+        pipe_line.finished = true;
+        let report = &pipe_line.report;
+        log::info!(
+            "pipe line finished: {} segments ({} straight, {} bent, {} split), {} manometers, {} hot spots, {} leaks, {} valves, {} capped",
+            report.segments(),
+            report.straight,
+            report.bent,
+            report.split,
+            report.manometers,
+            report.hot_spots,
+            report.leaks,
+            report.valves,
+            report.capped + pipe_line.current.len()
+        );
+        return;
+    }
+    // pick only once every pipe is placed, so the choice does not depend on how fast they
+    // attach
+    let placing = pipe_line
         .current
         .iter()
-        .enumerate()
-        .choose(&mut rand::thread_rng())
-    {
+        .any(|(parent, _)| matches!(pipes.get(*parent), Ok((_, _, ParentEntity::WaitForAttach))));
+    if placing {
+        return;
+    }
+
+    let rng = &mut world_seed.rng;
+    if let Some((i, &(parent, aid))) = pipe_line.current.iter().enumerate().choose(rng) {
+        let (rb_pos, attachment) = match pipes.get(parent) {
+            Ok((rb_pos, attachments, _)) => match attachments.0.get(&aid) {
+                Some(attachment) => (rb_pos, attachment),
+                None => return,
+            },
+            Err(..) => return,
+        };
         pipe_line.current.remove(i);
-        if rand::random() {
-            let mut straight = spawner.attachment(&item_collection.gray_pipe, aid, parent);
-            if rand::random() {
-                straight.attach(
-                    &item_collection.simple_manometer_icon,
-                    AttachmentPointId::Manometer,
-                );
+
+        let config = pipe_line.config.clone();
+        let segment = [
+            (PipeSegment::Straight, config.straight),
+            (PipeSegment::Bent, config.bent),
+            (PipeSegment::Split, config.split),
+        ]
+        .choose_weighted(rng, |(_, weight)| weight.max(0.0))
+        .map(|(segment, _)| *segment)
+        .unwrap_or(PipeSegment::Straight);
+        // fall back to the other shapes before giving up on the endpoint
+        let fits = |segment: &PipeSegment| {
+            let handle = match segment {
+                PipeSegment::Straight => &item_collection.gray_pipe,
+                PipeSegment::Bent => &item_collection.gray_pipe_bent,
+                PipeSegment::Split => &item_collection.gray_pipe_split,
+            };
+            items.get(handle).map_or(false, |item| {
+                segment_fits(
+                    &query_pipeline,
+                    &collider_set,
+                    &colliders,
+                    &config,
+                    item,
+                    (parent, rb_pos, attachment),
+                )
+            })
+        };
+        let segment = std::iter::once(segment)
+            .chain([PipeSegment::Straight, PipeSegment::Bent, PipeSegment::Split])
+            .find(|segment| fits(segment));
+
+        let report = &mut pipe_line.report;
+        let segment = match segment {
+            Some(segment) => segment,
+            None => {
+                report.capped += 1;
+                return;
             }
-            pipe_line.add_pipe(straight.build(&mut commands), vec![AttachmentPointId::Next]);
-        } else {
-            if rand::random() {
-                pipe_line.add_pipe(
+        };
+        // the spawner only draws its seed when something is placed, so ticks that wait for
+        // a pipe to attach do not change the layout
+        let spawner =
+            ItemSpawner::new(&items, &information_collection, &item_collection, rng.gen());
+        let (pipe, aids) = match segment {
+            PipeSegment::Straight => {
+                let mut straight = spawner.attachment(&item_collection.gray_pipe, aid, parent);
+                if rng.gen_bool(config.manometer_density.clamp(0.0, 1.0) as f64) {
+                    attach_manometer(&mut straight, &item_collection);
+                    report.manometers += 1;
+                }
+                if rng.gen_bool(config.hot_spot_density.clamp(0.0, 1.0) as f64) {
+                    straight.attach(&item_collection.hot_spot, AttachmentPointId::HotSpot);
+                    report.hot_spots += 1;
+                }
+                report.straight += 1;
+                let pipe = straight.seed(rng.gen()).build(&mut commands);
+                if rng.gen_bool(config.valve_density.clamp(0.0, 1.0) as f64) {
+                    commands.entity(pipe).insert(Valve::default());
+                    report.valves += 1;
+                }
+                (pipe, vec![AttachmentPointId::Next])
+            }
+            PipeSegment::Bent => {
+                report.bent += 1;
+                (
                     spawner
-                        .attachment(
-                            &item_collection.gray_pipe_bent,
-                            AttachmentPointId::Next,
-                            parent,
-                        )
+                        .attachment(&item_collection.gray_pipe_bent, aid, parent)
+                        .seed(rng.gen())
                         .build(&mut commands),
                     vec![AttachmentPointId::Next],
-                );
-            } else {
-                pipe_line.add_pipe(
+                )
+            }
+            PipeSegment::Split => {
+                report.split += 1;
+                (
                     spawner
-                        .attachment(
-                            &item_collection.gray_pipe_split,
-                            AttachmentPointId::Next,
-                            parent,
-                        )
+                        .attachment(&item_collection.gray_pipe_split, aid, parent)
+                        .seed(rng.gen())
                         .build(&mut commands),
                     vec![AttachmentPointId::Next, AttachmentPointId::Previous],
-                );
+                )
             }
+        };
+        if maybe_leak(&mut commands, pipe, config.leak_density, rng) {
+            report.leaks += 1;
         }
-    } else {
-        let transform = Transform::from_translation(Vec3::new(200.0, 40.0, 90.0));
-        *pipe_line = PipeLine::new(
-            spawner
-                .item(&item_collection.gray_pipe)
-                .transform(transform)
-                .build(&mut commands),
-            AttachmentPointId::Next,
-        );
+        pipe_line.add_pipe(pipe, aids);
     }
     // }
 }
+
+fn maybe_leak(commands: &mut Commands, pipe: Entity, density: f32, rng: &mut StdRng) -> bool {
+    let leak = rng.gen_bool(density.clamp(0.0, 1.0) as f64);
+    if leak {
+        commands.entity(pipe).insert(GasLeak {
+            rate: rng.gen_range(0.5..2.0),
+        });
+    }
+    leak
+}
+
+/// Tests the footprint a segment would have at the endpoint against the world bounds and
+/// all solid colliders except the ground, the robots and the pipe it attaches to. Robots
+/// drive through the pipe line, it does not grow around them.
+fn segment_fits(
+    query_pipeline: &QueryPipeline,
+    collider_set: &QueryPipelineColliderComponentsSet,
+    colliders: &Query<(&ColliderType, &ItemType, Option<&ParentEntity>)>,
+    config: &PipeLineConfig,
+    item: &LoadedItem,
+    (parent, rb_pos, attachment): (Entity, &RigidBodyPosition, &Attachment),
+) -> bool {
+    let parent_rotation = rb_pos.position.rotation.angle();
+    let (axis, angle) = attachment.transform.rotation.to_axis_angle();
+    let rotation = parent_rotation + angle * axis.z.signum();
+    let endpoint = world_position(rb_pos)
+        + Mat2::from_angle(parent_rotation) * attachment.transform.translation.truncate();
+    let center = endpoint - Mat2::from_angle(rotation) * Vec2::new(item.origin.0, item.origin.1);
+    if !config.in_bounds(center) {
+        return false;
+    }
+
+    // a little smaller than the sprite, so touching the neighbouring segments is fine
+    let half_extents = Vec2::new(item.sprite.size.0, item.sprite.size.1) * 0.4 / PHYSICS_SCALE;
+    let shape = ColliderShape::cuboid(half_extents.x, half_extents.y);
+    let robot_owned = |parent_entity: Option<&ParentEntity>| match parent_entity {
+        Some(ParentEntity::Robot(Some(root))) => {
+            matches!(colliders.get(*root), Ok((_, ItemType::Robot(_), _)))
+        }
+        _ => false,
+    };
+    let filter = |handle: ColliderHandle| {
+        let entity = handle.entity();
+        entity != parent
+            && match colliders.get(entity) {
+                Ok((collider_type, item_type, parent_entity)) => {
+                    *collider_type == ColliderType::Solid
+                        && *item_type != ItemType::Environment(EnvironmentItemType::Ground)
+                        && !robot_owned(parent_entity)
+                }
+                Err(..) => true,
+            }
+    };
+    query_pipeline
+        .intersection_with_shape(
+            collider_set,
+            &Isometry::new((center / PHYSICS_SCALE).into(), rotation),
+            &*shape,
+            InteractionGroups::all(),
+            Some(&filter),
+        )
+        .is_none()
+}
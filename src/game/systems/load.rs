@@ -1,12 +1,18 @@
 use bevy::{log, prelude::*, render::texture::FilterMode};
 use bevy_rapier2d::{na::Vector2, physics::RapierConfiguration};
+use rand::Rng;
 
 use crate::{
     consts::PHYSICS_SCALE,
     game::{
         builders::item::{ItemBuilder, ItemSpawner},
         components::robot::AttachmentPointId,
-        resources::{item_collection::*, item_information::*},
+        resources::{
+            facility_map::{FacilityMap, MapObjectKind},
+            item_collection::*,
+            item_information::*,
+            world_seed::WorldSeed,
+        },
     },
     GameState,
 };
@@ -57,17 +63,25 @@ pub fn fill_information(
     app_state.set(GameState::Game).unwrap();
 }
 
+pub fn configure_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.gravity = Vector2::zeros();
+    rapier_config.scale = PHYSICS_SCALE;
+}
+
 pub fn spawn_entities(
     mut commands: Commands,
     information_collection: Res<InformationCollection>,
     item_collection: Res<ItemCollection>,
     items: Res<Assets<LoadedItem>>,
-    mut rapier_config: ResMut<RapierConfiguration>,
+    facility_map: Res<FacilityMap>,
+    mut world_seed: ResMut<WorldSeed>,
 ) {
-    let mut spawner = ItemSpawner::new(&items, &information_collection, &item_collection);
-
-    rapier_config.gravity = Vector2::zeros();
-    rapier_config.scale = PHYSICS_SCALE;
+    let mut spawner = ItemSpawner::new(
+        &items,
+        &information_collection,
+        &item_collection,
+        world_seed.rng.gen(),
+    );
 
     // spawner
     //     .new()
@@ -75,9 +89,14 @@ pub fn spawn_entities(
     //     .transform(Transform::from_translation(Vec3::new(100.0, 0.0, 0.0)))
     //     .build(&mut commands);
 
+    let start = facility_map
+        .objects(MapObjectKind::RobotStart)
+        .next()
+        .map(|start| start.transform())
+        .unwrap_or_default();
     spawner
         .item(&item_collection.simple_body)
-        // .transform(Transform::from_translation(Vec3::new(0.0, 0.0, 90.0)))
+        .transform(start)
         // .select()
         .attach(
             &item_collection.camera_hd,
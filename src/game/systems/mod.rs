@@ -9,4 +9,14 @@ pub mod load;
 pub mod interaction_marker;
 pub mod debug;
 pub mod robot_commands;
-pub mod inspection;
\ No newline at end of file
+pub mod inspection;
+pub mod sensors;
+pub mod odometry;
+pub mod exploration;
+pub mod pressure;pub mod alarms;
+pub mod gas;
+pub mod surface;
+pub mod structures;
+pub mod scenario;
+pub mod save_game;
+pub mod offline;
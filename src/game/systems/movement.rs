@@ -1,6 +1,24 @@
-use crate::game::{components::robot::*, resources::robot_commands::*};
-use bevy::{log, prelude::*};
+use crate::{
+    game::{
+        builders::item::ItemSpawner,
+        components::robot::*,
+        resources::{
+            item_collection::*,
+            item_information::InformationCollection,
+            robot_commands::*,
+            surface_map::SurfaceMap,
+            world_seed::{SystemRng, WorldSeed},
+        },
+    },
+    utils::{cursor_world_position, world_position},
+};
+use bevy::{
+    log,
+    prelude::*,
+    render::camera::{Camera, OrthographicProjection},
+};
 use bevy_rapier2d::{physics::JointHandleComponent, prelude::*};
+use rand::Rng;
 
 // TODO: only allow selected to drive
 pub fn send_drive_robot(
@@ -103,8 +121,12 @@ pub fn zoom_cameras(
     });
 }
 
+/// Rapier creates the joint in the frame after the lens got attached, so this waits for it.
 pub fn set_initial_camera_lens(
-    query: Query<(&JointHandleComponent, &CameraLens, &ParentEntity), Changed<ParentEntity>>,
+    query: Query<
+        (&JointHandleComponent, &CameraLens, &ParentEntity),
+        Or<(Added<JointHandleComponent>, Changed<ParentEntity>)>,
+    >,
     mut robot_commands: ResMut<RobotCommands>,
 ) {
     query.for_each(|(joint_handle, camera_lens, parent_entity)| {
@@ -119,3 +141,189 @@ pub fn set_initial_camera_lens(
         });
     });
 }
+
+pub fn autopilot(
+    rapier_parameters: Res<RapierConfiguration>,
+    autopilots: Query<(
+        &Autopilot,
+        &RigidBodyPosition,
+        &ParentEntity,
+        Option<&PoseEstimate>,
+    )>,
+    waypoints: Query<&Transform, With<WaypointMarker>>,
+    range_sensors: Query<(&RangeSensor, &RigidBodyPosition, &ParentEntity)>,
+    drivable_query: Query<(Entity, &Motors, &ParentEntity)>,
+    mut robot_commands: ResMut<RobotCommands>,
+) {
+    autopilots.for_each(|(autopilot, rb_pos, parent_entity, estimate)| {
+        if !autopilot.enabled {
+            return;
+        }
+        let (position, heading) = robot_pose(rb_pos, estimate);
+        let target = match nearest_waypoint(&waypoints, position) {
+            Some(target) if target.distance(position) > autopilot.arrive_distance => target,
+            _ => return,
+        };
+        // follow the planned route towards the waypoint
+        let target = match autopilot.route.first() {
+            Some(corner) if autopilot.prefer_cheap_surfaces => *corner,
+            _ => target,
+        };
+
+        let forward = Vec2::new(-heading.sin(), heading.cos());
+        let to_target = target - position;
+        let mut steer = (forward.x * to_target.y - forward.y * to_target.x)
+            .atan2(forward.dot(to_target));
+
+        // turn away from the closest obstacle in front of the robot
+        let mut blocked = false;
+        range_sensors.for_each(|(sensor, sensor_pos, sensor_parent)| {
+            if sensor_parent != parent_entity {
+                return;
+            }
+            if let Some((angle, distance)) = sensor.nearest() {
+                let relative = normalize_angle(
+                    sensor_pos.position.rotation.angle() + angle - heading,
+                );
+                if distance < autopilot.avoid_distance
+                    && relative.abs() < std::f32::consts::FRAC_PI_2
+                {
+                    blocked = true;
+                    steer = if relative > 0.0 {
+                        -std::f32::consts::FRAC_PI_2
+                    } else {
+                        std::f32::consts::FRAC_PI_2
+                    };
+                }
+            }
+        });
+
+        drivable_query.for_each(|(entity, drive, track_parent)| {
+            if track_parent != parent_entity {
+                return;
+            }
+            if !blocked {
+                robot_commands.send(RobotCommand {
+                    robot_entity: *parent_entity,
+                    command: RobotCommandType::MoveMotors {
+                        entity,
+                        delta: Vec2::Y,
+                        torque: 0.0,
+                    },
+                    power_consumption: 10.0,
+                });
+            }
+            if steer.abs() > 0.05 {
+                let torque =
+                    steer.clamp(-1.0, 1.0) / rapier_parameters.scale * drive.angular_speed;
+                robot_commands.send(RobotCommand {
+                    robot_entity: *parent_entity,
+                    command: RobotCommandType::MoveMotors {
+                        entity,
+                        delta: Vec2::ZERO,
+                        torque,
+                    },
+                    power_consumption: torque.abs(),
+                });
+            }
+        });
+    });
+}
+
+/// Plans routes to the nearest waypoint that avoid expensive surfaces, corners are dropped
+/// once they are reached.
+pub fn plan_routes(
+    surface_map: Res<SurfaceMap>,
+    autopilots: Query<(&mut Autopilot, &RigidBodyPosition, Option<&PoseEstimate>)>,
+    waypoints: Query<&Transform, With<WaypointMarker>>,
+) {
+    autopilots.for_each_mut(|(mut autopilot, rb_pos, estimate)| {
+        if !autopilot.enabled || !autopilot.prefer_cheap_surfaces {
+            if !autopilot.route.is_empty() || autopilot.planned_for.is_some() {
+                autopilot.route.clear();
+                autopilot.planned_for = None;
+            }
+            return;
+        }
+        let (position, _) = robot_pose(rb_pos, estimate);
+        let target = match nearest_waypoint(&waypoints, position) {
+            Some(target) => target,
+            None => {
+                autopilot.route.clear();
+                autopilot.planned_for = None;
+                return;
+            }
+        };
+        if autopilot.planned_for != Some(target) || surface_map.is_changed() {
+            autopilot.route = surface_map.route(position, target);
+            autopilot.planned_for = Some(target);
+        }
+        let arrive_distance = autopilot.arrive_distance;
+        while autopilot.route.len() > 1 && autopilot.route[0].distance(position) < arrive_distance {
+            autopilot.route.remove(0);
+        }
+    });
+}
+
+fn nearest_waypoint(
+    waypoints: &Query<&Transform, With<WaypointMarker>>,
+    position: Vec2,
+) -> Option<Vec2> {
+    waypoints
+        .iter()
+        .map(|transform| transform.translation.truncate())
+        .min_by(|a, b| {
+            a.distance(position)
+                .partial_cmp(&b.distance(position))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+}
+
+/// Position in pixels and heading of a robot, as far as the robot itself knows.
+pub fn robot_pose(rb_pos: &RigidBodyPosition, estimate: Option<&PoseEstimate>) -> (Vec2, f32) {
+    match estimate {
+        Some(estimate) if estimate.use_estimate && estimate.initialized => {
+            (estimate.position, estimate.rotation)
+        }
+        _ => (
+            world_position(rb_pos),
+            rb_pos.position.rotation.angle(),
+        ),
+    }
+}
+
+fn normalize_angle(angle: f32) -> f32 {
+    let angle = angle % std::f32::consts::TAU;
+    if angle > std::f32::consts::PI {
+        angle - std::f32::consts::TAU
+    } else if angle < -std::f32::consts::PI {
+        angle + std::f32::consts::TAU
+    } else {
+        angle
+    }
+}
+
+/// `G` drops a waypoint at the cursor.
+pub fn add_waypoint(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    information_collection: Res<InformationCollection>,
+    item_collection: Res<ItemCollection>,
+    items: Res<Assets<LoadedItem>>,
+    world_seed: Res<WorldSeed>,
+    mut rng: Local<SystemRng>,
+    q_camera: Query<(&Transform, &OrthographicProjection), With<Camera>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::G) {
+        let win = windows.get_primary().expect("no primary window");
+        let (camera, projection) = q_camera.single().unwrap();
+        if let Some(position) = cursor_world_position(win, camera, projection) {
+            let seed = rng.get(&world_seed, "waypoints").gen();
+            ItemSpawner::new(&items, &information_collection, &item_collection, seed)
+                .item(&item_collection.waypoint_marker)
+                .transform(Transform::from_translation(position.extend(0.0)))
+                .build(&mut commands);
+        }
+    }
+}
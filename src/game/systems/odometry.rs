@@ -0,0 +1,200 @@
+use bevy::{prelude::*, utils::HashMap};
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+
+use crate::{
+    consts::PHYSICS_SCALE,
+    game::{
+        components::robot::*,
+        resources::{
+            ui::UiState,
+            world_seed::{SystemRng, WorldSeed},
+        },
+    },
+};
+
+/// Wheels slower than this, in pixels per second, count as standing still.
+const STILL_SPEED: f32 = 1.0;
+
+/// The commands of all tracks add up to a force and torque on the robot, which speeds it
+/// up against the damping of its parts like the physics does, the parts taken as point
+/// masses around the body.
+#[derive(Default)]
+struct Drive {
+    force: f32,
+    torque: f32,
+    mass: f32,
+    damping: f32,
+    inertia: f32,
+    turn_damping: f32,
+}
+
+pub fn wheel_odometry(
+    time: Res<Time>,
+    world_seed: Res<WorldSeed>,
+    mut rng: Local<SystemRng>,
+    parts: Query<(
+        &RigidBodyPosition,
+        &RigidBodyDamping,
+        &ItemMass,
+        &ParentEntity,
+    )>,
+    tracks: Query<(&mut WheelOdometry, &RigidBodyPosition, &ParentEntity), With<Motors>>,
+) {
+    let rng = rng.get(&world_seed, "wheel odometry");
+    let offset = |robot: Entity, rb_pos: &RigidBodyPosition| {
+        parts.get(robot).ok().map(|(robot_pos, ..)| {
+            robot_pos.position.rotation.inverse()
+                * (rb_pos.position.translation.vector - robot_pos.position.translation.vector)
+        })
+    };
+
+    let mut drives: HashMap<Entity, Drive> = HashMap::default();
+    tracks.for_each_mut(|(mut odometry, rb_pos, parent_entity)| {
+        if let ParentEntity::Robot(Some(robot)) = parent_entity {
+            let x = offset(*robot, rb_pos).map_or(0.0, |offset| offset.x);
+            let drive = drives.entry(*robot).or_default();
+            // a track pushing forward off the center turns the robot as well
+            drive.force += odometry.force;
+            drive.torque += odometry.torque + x * odometry.force;
+        }
+        odometry.force = 0.0;
+        odometry.torque = 0.0;
+    });
+    parts.for_each(|(rb_pos, rb_damping, mass, parent_entity)| {
+        if let ParentEntity::Robot(Some(robot)) = parent_entity {
+            if let (Some(drive), Some(offset)) = (drives.get_mut(robot), offset(*robot, rb_pos)) {
+                let inertia = mass.0 * offset.norm_squared();
+                drive.mass += mass.0;
+                drive.damping += mass.0 * rb_damping.linear_damping;
+                drive.inertia += inertia;
+                drive.turn_damping += inertia * rb_damping.linear_damping;
+            }
+        }
+    });
+
+    let dt = time.delta_seconds();
+    tracks.for_each_mut(|(mut odometry, rb_pos, parent_entity)| {
+        let (robot, drive) = match parent_entity {
+            ParentEntity::Robot(Some(robot)) => match drives.get(robot) {
+                Some(drive) => (*robot, drive),
+                None => return,
+            },
+            _ => return,
+        };
+        if drive.mass > 0.0 {
+            odometry.linear = (odometry.linear + drive.force / drive.mass * dt)
+                / (1.0 + drive.damping / drive.mass * dt);
+        }
+        if drive.inertia > 0.0 {
+            odometry.angular = (odometry.angular + drive.torque / drive.inertia * dt)
+                / (1.0 + drive.turn_damping / drive.inertia * dt);
+        }
+        let x = offset(robot, rb_pos).map_or(0.0, |offset| offset.x);
+        let slip = if odometry.slip > 0.0 {
+            1.0 + rng.gen_range(-odometry.slip..odometry.slip)
+        } else {
+            1.0
+        };
+        odometry.speed = (odometry.linear + odometry.angular * x) * PHYSICS_SCALE * slip;
+    });
+}
+
+pub fn estimate_pose(
+    time: Res<Time>,
+    robots: Query<(&mut PoseEstimate, &RigidBodyPosition, &ParentEntity)>,
+    tracks: Query<(&WheelOdometry, &RigidBodyPosition, &ParentEntity)>,
+) {
+    robots.for_each_mut(|(mut estimate, rb_pos, parent_entity)| {
+        if !estimate.initialized {
+            estimate.position = Vec2::new(
+                rb_pos.position.translation.x,
+                rb_pos.position.translation.y,
+            ) * PHYSICS_SCALE;
+            estimate.rotation = rb_pos.position.rotation.angle();
+            estimate.initialized = true;
+            return;
+        }
+
+        // differential drive: the lateral offset of each track turns speed differences into rotation
+        let wheels = tracks
+            .iter()
+            .filter(|(_, _, track_parent)| *track_parent == parent_entity)
+            .map(|(odometry, track_pos, _)| {
+                let offset = rb_pos.position.rotation.inverse()
+                    * (track_pos.position.translation.vector - rb_pos.position.translation.vector);
+                (offset.x * PHYSICS_SCALE, odometry.speed)
+            })
+            .collect::<Vec<_>>();
+        if wheels.is_empty() {
+            return;
+        }
+        let speed = wheels.iter().map(|(_, speed)| speed).sum::<f32>() / wheels.len() as f32;
+        let (left, right) = (
+            wheels
+                .iter()
+                .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal)),
+            wheels
+                .iter()
+                .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal)),
+        );
+        let angular_speed = match (left, right) {
+            (Some(left), Some(right)) if (right.0 - left.0).abs() > f32::EPSILON => {
+                (right.1 - left.1) / (right.0 - left.0)
+            }
+            _ => 0.0,
+        };
+
+        // the heading only drifts while the wheels turn
+        let drift = if wheels.iter().any(|(_, speed)| speed.abs() > STILL_SPEED) {
+            estimate.drift
+        } else {
+            0.0
+        };
+        let dt = time.delta_seconds();
+        estimate.rotation += (angular_speed + drift) * dt;
+        let heading = estimate.rotation;
+        estimate.position += Vec2::new(-heading.sin(), heading.cos()) * speed * dt;
+    });
+}
+
+pub fn spawn_pose_ghosts(
+    mut commands: Commands,
+    robots: Query<(Entity, &Handle<TextureAtlas>), Added<PoseEstimate>>,
+) {
+    robots.for_each(|(robot, atlas)| {
+        commands
+            .spawn_bundle(SpriteSheetBundle {
+                visible: Visible {
+                    is_visible: false,
+                    is_transparent: true,
+                },
+                sprite: TextureAtlasSprite {
+                    color: Color::rgba(0.4, 0.8, 1.0, 0.4),
+                    ..Default::default()
+                },
+                texture_atlas: atlas.clone(),
+                ..Default::default()
+            })
+            .insert(PoseGhost { robot });
+    });
+}
+
+pub fn update_pose_ghosts(
+    mut commands: Commands,
+    ui_state: Res<UiState>,
+    robots: Query<(&PoseEstimate, &Transform), Without<PoseGhost>>,
+    ghosts: Query<(Entity, &PoseGhost, &mut Transform, &mut Visible)>,
+) {
+    ghosts.for_each_mut(|(entity, ghost, mut transform, mut visible)| {
+        if let Ok((estimate, robot_transform)) = robots.get(ghost.robot) {
+            visible.is_visible = ui_state.show_pose_estimate && estimate.initialized;
+            transform.translation = estimate
+                .position
+                .extend(robot_transform.translation.z + 1.0);
+            transform.rotation = Quat::from_axis_angle(Vec3::Z, estimate.rotation);
+        } else {
+            commands.entity(entity).despawn();
+        }
+    });
+}
@@ -0,0 +1,309 @@
+use std::cmp::Ordering;
+
+use bevy::{log, prelude::*};
+use bevy_egui::{egui, EguiContext};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    game::{
+        components::robot::*,
+        resources::{
+            incident_log::{FaultSchedule, Incident, IncidentKind, IncidentLog},
+            offline::{OfflineProgress, OfflineReport},
+            robot_commands::RobotCommands,
+            save_game::SaveGames,
+            ui::UiState,
+            world_seed::WorldSeed,
+        },
+        systems::alarms::roll_fault,
+        types::PressureUnit,
+    },
+    utils::format_duration,
+};
+
+struct OfflineTarget {
+    entity: Entity,
+    seed: u64,
+    /// The unit of a manometer, hot spots have none.
+    unit: Option<PressureUnit>,
+    last_inspected: Option<f64>,
+    interval: f32,
+    /// The fault the manometer shows and when it began.
+    fault: Option<(FaultKind, f64)>,
+}
+
+struct OfflineRobot {
+    battery: Entity,
+    charge: f32,
+    capacity: f32,
+    charge_speed: f32,
+    charging: bool,
+    /// Seconds spent on the next inspection.
+    progress: f64,
+}
+
+/// Lets the autonomous robots of a loaded save work through the time the game was closed.
+/// Each step they draw power and inspect the most overdue targets, drained robots charge
+/// when there is a charging station, and manometers develop faults that count as missed
+/// when nobody inspects them in time. Faults still open on return stay on the manometer.
+pub fn simulate_offline(
+    mut commands: Commands,
+    time: Res<Time>,
+    save_games: Res<SaveGames>,
+    mut offline: ResMut<OfflineProgress>,
+    mut world_seed: ResMut<WorldSeed>,
+    fault_schedule: Res<FaultSchedule>,
+    mut ui_state: ResMut<UiState>,
+    mut robot_commands: ResMut<RobotCommands>,
+    mut incident_log: ResMut<IncidentLog>,
+    robots: Query<(Entity, &Autopilot)>,
+    stations: Query<&ChargingStation>,
+    mut batteries: Query<(Entity, &mut Battery, &ParentEntity)>,
+    mut manometers: QuerySet<(
+        Query<(Entity, &Manometer, &ItemSeed)>,
+        Query<&mut Manometer>,
+    )>,
+    mut hot_spots: QuerySet<(Query<(Entity, &HotSpot, &ItemSeed)>, Query<&mut HotSpot>)>,
+) {
+    let away = match offline.pending {
+        Some(away) if save_games.restoring.is_empty() => away,
+        _ => return,
+    };
+    offline.pending = None;
+    let now = time.seconds_since_startup();
+    let simulated = away.clamp(0.0, offline.max_away);
+    let mut report = OfflineReport {
+        away,
+        simulated,
+        ..Default::default()
+    };
+
+    // everything aged by the time away, the inspections below happen within it
+    let age = |last_inspected: Option<f64>| last_inspected.map(|last| last - simulated);
+    let mut targets = manometers
+        .q0()
+        .iter()
+        .map(|(entity, manometer, seed)| OfflineTarget {
+            entity,
+            seed: seed.0,
+            unit: Some(manometer.unit),
+            last_inspected: age(manometer.last_inspected),
+            interval: manometer.interval,
+            fault: None,
+        })
+        .collect::<Vec<_>>();
+    targets.extend(
+        hot_spots
+            .q0()
+            .iter()
+            .map(|(entity, hot_spot, seed)| OfflineTarget {
+                entity,
+                seed: seed.0,
+                unit: None,
+                last_inspected: age(hot_spot.last_inspected),
+                interval: hot_spot.interval,
+                fault: None,
+            }),
+    );
+
+    let mut fleet = robots
+        .iter()
+        .filter(|(_, autopilot)| autopilot.enabled)
+        .filter_map(|(robot, _)| {
+            batteries
+                .iter_mut()
+                .find(|(_, _, parent)| **parent == ParentEntity::Robot(Some(robot)))
+                .map(|(entity, battery, _)| OfflineRobot {
+                    battery: entity,
+                    charge: battery.charge,
+                    capacity: battery.capacity,
+                    charge_speed: battery.charge_speed,
+                    charging: false,
+                    progress: 0.0,
+                })
+        })
+        .collect::<Vec<_>>();
+    report.robots = fleet.len();
+
+    let can_charge = stations.iter().next().is_some();
+    let fault_chance = (fault_schedule.rate as f64 / 60.0 * offline.step).clamp(0.0, 1.0);
+    let deadline = fault_schedule.deadline as f64;
+    let mut rng = StdRng::seed_from_u64(world_seed.rng.gen());
+    let mut record = |kind: IncidentKind, target: &OfflineTarget, unit: PressureUnit, at: f64| {
+        incident_log.record(Incident {
+            kind,
+            manometer: target.seed,
+            value: None,
+            unit,
+            timestamp: at,
+        });
+    };
+
+    let mut t = now - simulated;
+    while t < now && offline.step > 0.0 {
+        let dt = offline.step.min(now - t);
+        t += dt;
+        for target in &mut targets {
+            let unit = match target.unit {
+                Some(unit) => unit,
+                None => continue,
+            };
+            match target.fault {
+                Some((kind, started)) if t - started >= deadline => {
+                    target.fault = None;
+                    report.faults_missed += 1;
+                    record(IncidentKind::Missed(kind), target, unit, t);
+                }
+                None if rng.gen_bool(fault_chance) => {
+                    let kind = if rng.gen() {
+                        FaultKind::Drift
+                    } else {
+                        FaultKind::Spike
+                    };
+                    target.fault = Some((kind, t));
+                }
+                _ => {}
+            }
+        }
+
+        for robot in &mut fleet {
+            if robot.charging {
+                let charged = (robot.charge_speed * dt as f32).min(robot.capacity - robot.charge);
+                robot.charge += charged;
+                report.energy_charged += charged;
+                robot.charging = robot.charge < robot.capacity;
+                continue;
+            }
+            if robot.charge <= 0.0 {
+                continue;
+            }
+            let used = (offline.power * dt as f32).min(robot.charge);
+            robot.charge -= used;
+            report.energy_used += used;
+            robot.charging = robot.charge <= 0.0 && can_charge;
+            robot.progress += dt;
+            while robot.progress >= offline.inspection_time {
+                let next = targets
+                    .iter_mut()
+                    .filter(|target| is_due(target, t))
+                    .min_by(|a, b| {
+                        a.last_inspected
+                            .partial_cmp(&b.last_inspected)
+                            .unwrap_or(Ordering::Equal)
+                    });
+                let target = match next {
+                    Some(next) => next,
+                    // nothing is due, the robot waits on its route
+                    None => {
+                        robot.progress = offline.inspection_time;
+                        break;
+                    }
+                };
+                robot.progress -= offline.inspection_time;
+                target.last_inspected = Some(t);
+                match (target.unit, target.fault.take()) {
+                    (Some(unit), Some((kind, _))) => {
+                        report.manometers_inspected += 1;
+                        report.faults_detected += 1;
+                        record(IncidentKind::Detected(kind), target, unit, t);
+                    }
+                    (Some(..), None) => report.manometers_inspected += 1,
+                    (None, _) => report.hot_spots_inspected += 1,
+                }
+            }
+        }
+    }
+
+    for robot in &fleet {
+        if let Ok((_, mut battery, _)) = batteries.get_mut(robot.battery) {
+            battery.charge = robot.charge;
+        }
+        if robot.charge <= 0.0 && !can_charge {
+            report.robots_drained += 1;
+        }
+    }
+    for target in &targets {
+        if let Ok(mut manometer) = manometers.q1_mut().get_mut(target.entity) {
+            manometer.last_inspected = target.last_inspected;
+            // faults nobody found yet carry on in the game, with the deadline they had
+            if let Some((kind, started)) = target.fault {
+                report.faults_open += 1;
+                let fault = roll_fault(&mut rng, &manometer, kind, started, started + deadline);
+                commands.entity(target.entity).insert(fault);
+            }
+        } else if let Ok(mut hot_spot) = hot_spots.q1_mut().get_mut(target.entity) {
+            hot_spot.last_inspected = target.last_inspected;
+        }
+    }
+    report.targets_due = targets.iter().filter(|target| is_due(target, now)).count();
+
+    ui_state.manometers_inspected += report.manometers_inspected;
+    ui_state.hot_spots_inspected += report.hot_spots_inspected;
+    robot_commands.energy_used += report.energy_used;
+    log::info!(
+        "away for {}, simulated {}: {} robots, {} inspections, {} faults missed",
+        format_duration(away),
+        format_duration(simulated),
+        report.robots,
+        report.manometers_inspected + report.hot_spots_inspected,
+        report.faults_missed
+    );
+    offline.report = Some(report);
+}
+
+fn is_due(target: &OfflineTarget, now: f64) -> bool {
+    InspectionStatus::from_schedule(target.last_inspected, target.interval, now)
+        != InspectionStatus::Ok
+}
+
+pub fn offline_report_ui(egui_ctx: ResMut<EguiContext>, mut offline: ResMut<OfflineProgress>) {
+    let report = match &offline.report {
+        Some(report) => report.clone(),
+        None => return,
+    };
+    let mut open = true;
+    egui::Window::new("While you were away")
+        .default_pos((500.0, 200.0))
+        .collapsible(false)
+        .open(&mut open)
+        .show(egui_ctx.ctx(), |ui| {
+            ui.label(format!(
+                "You were away for {}.",
+                format_duration(report.away)
+            ));
+            if report.is_capped() {
+                ui.label(format!(
+                    "Only the first {} count.",
+                    format_duration(report.simulated)
+                ));
+            }
+            ui.separator();
+            if report.robots == 0 {
+                ui.label("No robot was on autopilot.");
+            } else {
+                ui.label(format!("{} robots on autopilot", report.robots));
+            }
+            ui.label(format!(
+                "Inspected {} manometers and {} hot spots",
+                report.manometers_inspected, report.hot_spots_inspected
+            ));
+            ui.label(format!(
+                "Used {:.0} energy, charged {:.0}",
+                report.energy_used, report.energy_charged
+            ));
+            if report.robots_drained > 0 {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!("{} robots ran out of charge", report.robots_drained),
+                );
+            }
+            ui.label(format!(
+                "Faults detected {} / missed {} / still open {}",
+                report.faults_detected, report.faults_missed, report.faults_open
+            ));
+            ui.label(format!("{} targets are due now", report.targets_due));
+        });
+    if !open {
+        offline.report = None;
+    }
+}
@@ -1,16 +1,14 @@
 use crate::consts::PHYSICS_SCALE;
-use crate::dev::debug;
 use crate::game::components::robot::*;
 use crate::game::types::*;
-use bevy::{log, prelude::*};
-use bevy_prototype_debug_lines::*;
+use crate::utils::world_position;
+use bevy::{log, prelude::*, utils::HashMap};
 use bevy_rapier2d::na::Isometry2;
 use bevy_rapier2d::physics::JointHandleComponent;
 use bevy_rapier2d::prelude::*;
 use bevy_rapier2d::rapier::na::Vector2;
 
 pub fn reduce_sideways_vel(
-    mut lines: ResMut<DebugLines>,
     _rapier_parameters: Res<RapierConfiguration>,
     mut player_info: Query<
         (
@@ -24,7 +22,7 @@ pub fn reduce_sideways_vel(
         Changed<RigidBodyVelocity>,
     >,
 ) {
-    for (_drive, _forces, mut rb_vel, pos, trans, _attach) in player_info.iter_mut() {
+    for (_drive, _forces, mut rb_vel, pos, _trans, _attach) in player_info.iter_mut() {
         let dir = pos.position.transform_vector(&Vector2::y());
         let angle = dir.angle(&rb_vel.linvel);
         let projected = rb_vel.linvel.magnitude() * angle.cos();
@@ -34,17 +32,20 @@ pub fn reduce_sideways_vel(
         let t = 0.5;
         let damped = res * t + rb_vel.linvel * (1. - t);
 
-        debug::relative_line(&mut lines, trans, Vec2::new(damped.x, damped.y));
-        debug::relative_line(&mut lines, trans, Vec2::new(dir.x, dir.y));
-
         rb_vel.linvel = damped;
     }
 }
 
-pub fn adjust_damping(damping: Query<(&Motors, &mut RigidBodyDamping), Changed<Motors>>) {
-    damping.for_each_mut(|(driver, mut rb_damping)| {
-        rb_damping.linear_damping = driver.linear_damping;
-        rb_damping.angular_damping = driver.angular_damping;
+pub fn adjust_damping(
+    damping: Query<
+        (&Motors, Option<&Traction>, &mut RigidBodyDamping),
+        Or<(Changed<Motors>, Changed<Traction>)>,
+    >,
+) {
+    damping.for_each_mut(|(driver, traction, mut rb_damping)| {
+        let resistance = traction.map_or(1.0, |traction| traction.rolling_resistance);
+        rb_damping.linear_damping = driver.linear_damping * resistance;
+        rb_damping.angular_damping = driver.angular_damping * resistance;
     });
 }
 
@@ -154,12 +155,22 @@ pub fn spawn_joints(
                 }
             }
             WantToAttach::Me => {
+                let placement = tag_queries
+                    .q0()
+                    .get(entity)
+                    .map(|(_, transform, _)| {
+                        Isometry2::new(
+                            (transform.translation.truncate() / PHYSICS_SCALE).into(),
+                            transform.rotation.to_axis_angle().1,
+                        )
+                    })
+                    .unwrap_or_else(|_| Isometry2::identity());
                 parent_tags.push((
                     entity,
                     (
                         None,
                         90.0,
-                        Isometry2::translation(item_origin.0, item_origin.1),
+                        placement * Isometry2::translation(item_origin.0, item_origin.1),
                     ),
                 ));
             }
@@ -185,6 +196,40 @@ pub fn spawn_joints(
         });
 }
 
+/// Sums up the attached items of each robot.
+pub fn update_robot_mass(
+    parts: Query<(&ItemMass, &ParentEntity, &RigidBodyPosition)>,
+    bodies: Query<(Entity, &mut RobotMass, &ItemMass, &RigidBodyPosition)>,
+) {
+    let mut robots: HashMap<Entity, (f32, Vec2)> = HashMap::default();
+    parts.for_each(|(mass, parent_entity, rb_pos)| {
+        if let ParentEntity::Robot(Some(robot)) = parent_entity {
+            let (total, moment) = robots.entry(*robot).or_insert((0.0, Vec2::ZERO));
+            *total += mass.0;
+            *moment += world_position(rb_pos) * mass.0;
+        }
+    });
+    bodies.for_each_mut(|(entity, mut robot_mass, body_mass, rb_pos)| {
+        let (total, moment) = robots.get(&entity).copied().unwrap_or_default();
+        if total <= 0.0 {
+            return;
+        }
+        let offset = moment / total - world_position(rb_pos);
+        let center_of_mass = Quat::from_rotation_z(-rb_pos.position.rotation.angle())
+            .mul_vec3(offset.extend(0.0))
+            .truncate();
+        let payload = total - body_mass.0;
+        if robot_mass.total != total
+            || robot_mass.payload != payload
+            || robot_mass.center_of_mass.distance(center_of_mass) > 0.1
+        {
+            robot_mass.total = total;
+            robot_mass.payload = payload;
+            robot_mass.center_of_mass = center_of_mass;
+        }
+    });
+}
+
 pub fn set_collision_for_item_types(
     query: Query<
         (
@@ -233,6 +278,15 @@ pub fn set_collision_for_item_types(
                     *mass = ColliderMassProps::Density(0.0001);
                     sprite.color = Color::rgba(0.0, 0.2, 1.0, 0.1);
                 }
+                ItemType::Manometer(
+                    ManometerItemType::Background
+                    | ManometerItemType::Frame
+                    | ManometerItemType::Markings
+                    | ManometerItemType::Pointer,
+                ) => {
+                    *collider_type = ColliderType::Sensor;
+                    *mass = ColliderMassProps::Density(0.0001);
+                }
                 _ => {
                     *collider_type = ColliderType::Solid;
                 }
@@ -0,0 +1,133 @@
+use bevy::{prelude::*, utils::HashSet};
+use bevy_rapier2d::{physics::JointHandleComponent, prelude::*};
+use rand::Rng;
+
+use crate::game::{
+    components::robot::*,
+    resources::{
+        pipe_network::*,
+        world_seed::{SystemRng, WorldSeed},
+    },
+    types::{EnvironmentItemType, ItemType},
+};
+
+const POINTER_SWEEP: f32 = 270.0;
+const SOLVER_ITERATIONS: usize = 20;
+
+pub fn update_pipe_network(
+    mut network: ResMut<PipeNetwork>,
+    pipes: Query<(
+        Entity,
+        &ItemType,
+        &AttachmentMap<Attachment>,
+        Option<&PipeSource>,
+        Option<&Valve>,
+        Option<&GasLeak>,
+    )>,
+) {
+    let mut segments = HashSet::default();
+    let mut connections = vec![];
+    pipes.for_each(|(entity, item_type, attachments, source, valve, leak)| {
+        if *item_type != ItemType::Environment(EnvironmentItemType::Pipe) {
+            return;
+        }
+        segments.insert(entity);
+        let mut outflow = leak.map_or(0.0, |leak| leak.rate * LEAK_CONDUCTANCE);
+        for aid in [AttachmentPointId::Next, AttachmentPointId::Previous] {
+            let attachment = attachments.0.get(&aid);
+            match attachment.map(|attachment| attachment.attached) {
+                Some(Some((other, _))) => connections.push((entity, other)),
+                // open endpoints are consumers
+                Some(None) => outflow += SINK_CONDUCTANCE,
+                None => {}
+            }
+        }
+        network.insert(
+            entity,
+            PipeNode {
+                source: source.map(|source| source.head),
+                outflow,
+                open: valve.map_or(true, |valve| valve.open),
+                ..Default::default()
+            },
+        );
+    });
+    network.nodes.retain(|entity, _| segments.contains(entity));
+    for (a, b) in connections {
+        network.connect(a, b);
+    }
+    network.solve(SOLVER_ITERATIONS);
+}
+
+pub fn simulate_pressure(
+    time: Res<Time>,
+    world_seed: Res<WorldSeed>,
+    mut rng: Local<SystemRng>,
+    network: Res<PipeNetwork>,
+    query: Query<(Entity, &mut Manometer, &PressureSource, Option<&Fault>)>,
+    mounts: Query<&JointHandleComponent>,
+) {
+    let rng = rng.get(&world_seed, "pressure");
+    let seconds = time.seconds_since_startup();
+    query.for_each_mut(|(entity, mut manometer, source, fault)| {
+        // manometers sample the head of the segment they are mounted on
+        let head = mounts
+            .get(entity)
+            .ok()
+            .and_then(|joint| network.head(joint.entity1()))
+            .unwrap_or(1.0);
+        let noise = if source.noise > 0.0 {
+            rng.gen_range(-source.noise..source.noise)
+        } else {
+            0.0
+        };
+        let fault = fault
+            .map(|fault| fault.offset_at(seconds))
+            .unwrap_or_default();
+        // a low head pulls the pressure towards the bottom of the allowed band, without
+        // leaving it, only faults read out of band
+        let low = manometer.allowed.0 + source.noise;
+        let nominal = source.pressure_at(seconds as f32);
+        let network = low + (nominal - low).max(0.0) * head.clamp(0.0, 1.0);
+        manometer.pressure = network + noise + fault;
+    });
+}
+
+pub fn manometer_pointers(
+    manometers: Query<(&Manometer, &AttachmentMap<Attachment>)>,
+    gauge_parts: Query<&AttachmentMap<Attachment>, Without<Manometer>>,
+    pointers: Query<&JointHandleComponent>,
+    mut joint_set: ResMut<JointSet>,
+) {
+    manometers.for_each(|(manometer, attachments)| {
+        let mut pointer = attached_item(attachments, AttachmentPointId::ManometerBackground);
+        for aid in [
+            AttachmentPointId::ManometerFrame,
+            AttachmentPointId::ManometerMarkings,
+            AttachmentPointId::ManometerPointer,
+        ] {
+            pointer = pointer
+                .and_then(|item| gauge_parts.get(item).ok())
+                .and_then(|attachments| attached_item(attachments, aid));
+        }
+
+        if let Some(Ok(joint_handle)) = pointer.map(|pointer| pointers.get(pointer)) {
+            // the dial starts bottom left and sweeps clockwise
+            let angle = (POINTER_SWEEP / 2.0 - POINTER_SWEEP * manometer.dial_fraction())
+                .to_radians();
+            if let Some(joint) = joint_set.get_mut(joint_handle.handle()) {
+                if let JointParams::BallJoint(ref mut ball_joint) = joint.params {
+                    ball_joint.configure_motor_position(Rotation::from_angle(angle), 0.5, 0.5);
+                }
+            }
+        }
+    });
+}
+
+fn attached_item(attachments: &AttachmentMap<Attachment>, aid: AttachmentPointId) -> Option<Entity> {
+    attachments
+        .0
+        .get(&aid)
+        .and_then(|attachment| attachment.attached)
+        .map(|(item, _)| item)
+}
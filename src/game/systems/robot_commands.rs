@@ -3,24 +3,84 @@ use bevy_rapier2d::{physics::JointHandleComponent, prelude::*};
 
 use crate::{
     consts::PHYSICS_SCALE,
-    game::{components::robot::*, resources::robot_commands::*},
+    game::{
+        components::robot::*,
+        resources::{
+            compliance::FacilityCompliance, inspection_log::InspectionLog, robot_commands::*,
+        },
+    },
+    utils::world_position,
 };
 
+// robots heavier than this use more power for driving
+const NOMINAL_ROBOT_MASS: f32 = 20.0;
+
+/// Charges the batteries of robots within range of a charging station.
+pub fn charge_batteries(
+    time: Res<Time>,
+    stations: Query<(&ChargingStation, &RigidBodyPosition)>,
+    batteries: Query<(&mut Battery, &RigidBodyPosition)>,
+) {
+    batteries.for_each_mut(|(mut battery, battery_pos)| {
+        let position = world_position(battery_pos);
+        let in_range = stations.iter().any(|(station, station_pos)| {
+            world_position(station_pos).distance(position) <= station.range
+        });
+        if in_range {
+            battery.charge = (battery.charge + battery.charge_speed * time.delta_seconds())
+                .min(battery.capacity);
+        }
+    });
+}
+
 pub fn handle_command(
     batteries: Query<(&mut Battery, &ParentEntity)>,
-    mut drivable_entities: Query<(&mut RigidBodyForces, &Motors, &Transform)>,
+    mut drivable_entities: Query<(
+        &mut RigidBodyForces,
+        &Motors,
+        &Transform,
+        Option<&Traction>,
+        Option<&mut WheelOdometry>,
+    )>,
+    mut powered_entities: Query<&mut Powered>,
+    mut doors: Query<(&mut Door, &RigidBodyPosition)>,
+    bodies: Query<(&RigidBodyPosition, Option<&RobotMass>)>,
     mut joint_set: ResMut<JointSet>,
     mut robot_commands: ResMut<RobotCommands>,
+    compliance: Res<FacilityCompliance>,
+    inspection_log: Res<InspectionLog>,
 ) {
-    robot_commands.queue.drain(..).for_each(|robot_command| {
-        let mut consumption = robot_command.power_consumption;
+    let RobotCommands { queue, energy_used } = &mut *robot_commands;
+    queue.drain(..).for_each(|robot_command| {
+        let robot = match robot_command.robot_entity {
+            ParentEntity::Robot(Some(robot)) => bodies.get(robot).ok(),
+            _ => None,
+        };
+        let robot_mass = robot.and_then(|(_, mass)| mass);
+        // driving heavy robots and on rough ground costs more
+        let power_cost = match robot_command.command {
+            RobotCommandType::MoveMotors { entity, .. } => {
+                drivable_entities
+                    .get_mut(entity)
+                    .ok()
+                    .and_then(|(_, _, _, traction, _)| traction.map(|traction| traction.power_cost))
+                    .unwrap_or(1.0)
+                    * robot_mass
+                        .filter(|mass| mass.total > 0.0)
+                        .map_or(1.0, |mass| mass.total / NOMINAL_ROBOT_MASS)
+            }
+            _ => 1.0,
+        };
+        let mut consumption = robot_command.power_consumption * power_cost;
         batteries.for_each_mut(|(mut battery, parent)| {
             if *parent == robot_command.robot_entity {
                 if battery.charge >= consumption {
                     battery.charge -= consumption;
+                    *energy_used += consumption;
                     consumption = 0.0;
                 } else {
                     consumption -= battery.charge;
+                    *energy_used += battery.charge;
                     battery.charge = 0.0;
                 }
             }
@@ -34,14 +94,21 @@ pub fn handle_command(
                 } => {
                     drivable_entities
                         .get_mut(entity)
-                        .map(|(ref mut rb, drive, transform)| {
+                        .map(|(ref mut rb, drive, transform, traction, odometry)| {
+                            let friction = traction.map_or(1.0, |traction| traction.friction)
+                                * robot_mass.map_or(1.0, RobotMass::motor_efficiency);
                             let move_delta = delta.normalize_or_zero() / PHYSICS_SCALE;
+                            if let Some(mut odometry) = odometry {
+                                odometry.force += move_delta.y * drive.linear_speed * friction;
+                                odometry.torque += torque;
+                            }
                             if move_delta.length() > 0.0 {
                                 let force = transform
                                     .rotation
                                     .mul_vec3(move_delta.extend(0.0))
                                     .truncate()
-                                    * drive.linear_speed;
+                                    * drive.linear_speed
+                                    * friction;
                                 if force.length() > 0.0 {
                                     rb.force = force.into();
                                 }
@@ -99,6 +166,29 @@ pub fn handle_command(
                             }
                         });
                 }
+                RobotCommandType::Sense { entity } => {
+                    powered_entities
+                        .get_mut(entity)
+                        .map(|mut powered| powered.0 = true)
+                        .ok();
+                }
+                RobotCommandType::OperateDoor { door, open } => {
+                    if let (Ok((mut door, door_pos)), Some((robot_pos, _))) =
+                        (doors.get_mut(door), robot)
+                    {
+                        let distance =
+                            world_position(door_pos).distance(world_position(robot_pos));
+                        if distance > door.reach || door.open == open {
+                            return;
+                        }
+                        if open && !door.allows(compliance.fraction(), inspection_log.count()) {
+                            door.denied = true;
+                            return;
+                        }
+                        door.open = open;
+                        door.denied = false;
+                    }
+                }
             }
         }
     });
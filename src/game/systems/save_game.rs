@@ -0,0 +1,493 @@
+use bevy::{app::AppExit, log, prelude::*};
+use bevy_egui::{egui, EguiContext};
+use bevy_rapier2d::prelude::RigidBodyPosition;
+
+use crate::{
+    game::{
+        builders::item::{ItemBuilder, ItemSpawner},
+        components::robot::*,
+        resources::{
+            exploration_map::ExplorationMap, facility_map::FacilityMap, incident_log::IncidentLog,
+            inspection_log::InspectionLog, item_collection::*,
+            item_information::InformationCollection, offline::OfflineProgress, pipe_line::PipeLine,
+            robot_commands::RobotCommands, save_game::*, ui::UiState, world_seed::WorldSeed,
+        },
+    },
+    utils::format_duration,
+    GameState,
+};
+
+type ItemState<'a> = (
+    &'a ItemName,
+    &'a ItemSeed,
+    &'a AttachmentMap<Attachment>,
+    Option<&'a Battery>,
+    Option<&'a Manometer>,
+    Option<&'a HotSpot>,
+    Option<&'a Door>,
+    Option<&'a Valve>,
+    Option<&'a Fault>,
+);
+
+/// Handles save requests, the autosave and saves once more when the game is closed. Runs
+/// last, so the save sees the whole frame.
+pub fn save_game(
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+    mut save_games: ResMut<SaveGames>,
+    mut app_exit_events: EventReader<AppExit>,
+    world_seed: Res<WorldSeed>,
+    facility_map: Res<FacilityMap>,
+    ui_state: Res<UiState>,
+    inspection_log: Res<InspectionLog>,
+    incident_log: Res<IncidentLog>,
+    robot_commands: Res<RobotCommands>,
+    exploration_map: Res<ExplorationMap>,
+    roots: Query<(
+        Entity,
+        &ParentEntity,
+        &Transform,
+        &RigidBodyPosition,
+        Option<&Autopilot>,
+        Option<&PipeSource>,
+    )>,
+    items: Query<ItemState>,
+) {
+    if state.current() != &GameState::Game {
+        return;
+    }
+    let autosave = save_games.autosave.tick(time.delta()).just_finished();
+    let exiting = app_exit_events.iter().next().is_some();
+    let slot = match save_games.request {
+        Some(SaveRequest::Save(slot)) => slot,
+        _ if autosave || exiting => SaveSlot::Auto,
+        _ => return,
+    };
+    if save_games.request == Some(SaveRequest::Save(slot)) {
+        save_games.request = None;
+    }
+
+    let offset = save_games.time_offset;
+    let play_time = save_games.play_time(&time);
+    let mut readings = inspection_log.readings.clone();
+    readings
+        .iter_mut()
+        .for_each(|reading| reading.timestamp += offset);
+    let mut thermal = inspection_log.thermal.clone();
+    thermal
+        .iter_mut()
+        .for_each(|reading| reading.timestamp += offset);
+
+    let save_game = SaveGame {
+        version: SAVE_VERSION,
+        saved_at: unix_time(),
+        play_time,
+        seed: world_seed.seed,
+        map: facility_map.path.clone(),
+        items: roots
+            .iter()
+            .filter(|(entity, parent, ..)| **parent == ParentEntity::Robot(Some(*entity)))
+            .filter_map(|(entity, _, transform, rb_pos, autopilot, pipe_source)| {
+                Some(SavedRoot {
+                    position: transform.translation,
+                    rotation: rb_pos.position.rotation.angle().to_degrees(),
+                    autopilot: autopilot.map(|autopilot| SavedAutopilot {
+                        enabled: autopilot.enabled,
+                        prefer_cheap_surfaces: autopilot.prefer_cheap_surfaces,
+                    }),
+                    pipe_source: pipe_source.map(|pipe_source| pipe_source.head),
+                    item: save_item(entity, None, &items, offset)?,
+                })
+            })
+            .collect(),
+        statistics: SavedStatistics {
+            manometers_inspected: ui_state.manometers_inspected,
+            hot_spots_inspected: ui_state.hot_spots_inspected,
+            energy_used: robot_commands.energy_used,
+            detected: incident_log.detected,
+            false_alarms: incident_log.false_alarms,
+            missed: incident_log.missed,
+            readings,
+            thermal,
+        },
+        explored: exploration_map
+            .explored
+            .iter()
+            .enumerate()
+            .filter(|(_, explored)| **explored)
+            .map(|(cell, _)| cell)
+            .collect(),
+    };
+    save_games.status = match save_game.save(save_games.path(slot)) {
+        Ok(()) => format!("Saved to {}", slot),
+        Err(error) => {
+            log::error!("could not save to {}: {}", slot, error);
+            format!("Could not save to {}: {}", slot, error)
+        }
+    };
+    save_games.refresh();
+}
+
+fn save_item(
+    entity: Entity,
+    at: Option<AttachmentPointId>,
+    items: &Query<ItemState>,
+    offset: f64,
+) -> Option<SavedItem> {
+    let (name, seed, attachments, battery, manometer, hot_spot, door, valve, fault) =
+        items.get(entity).ok()?;
+    let play_time = |timestamp: Option<f64>| timestamp.map(|timestamp| timestamp + offset);
+    Some(SavedItem {
+        item: name.0.clone(),
+        at,
+        seed: Some(seed.0),
+        charge: battery.map(|battery| battery.charge),
+        manometer: manometer.map(|manometer| SavedInspection {
+            progress: manometer.progress,
+            quality: manometer.quality,
+            reading: manometer.reading,
+            last_inspected: play_time(manometer.last_inspected),
+        }),
+        hot_spot: hot_spot.map(|hot_spot| SavedInspection {
+            progress: hot_spot.progress,
+            quality: hot_spot.quality,
+            reading: hot_spot.reading,
+            last_inspected: play_time(hot_spot.last_inspected),
+        }),
+        open: door.map(|door| door.open),
+        valve: valve.map(|valve| valve.open),
+        fault: fault.map(|fault| SavedFault {
+            kind: fault.kind,
+            offset: fault.offset,
+            started: fault.started + offset,
+            deadline: fault.deadline + offset,
+        }),
+        parts: attachments
+            .0
+            .iter()
+            .filter_map(|(aid, attachment)| {
+                let (item, _) = attachment.attached?;
+                save_item(item, Some(*aid), items, offset)
+            })
+            .collect(),
+    })
+}
+
+/// Replaces every item with the ones of the requested save. Runs before the game phases,
+/// so the new items are there when `spawn_joints` attaches them. `PreUpdate` has no state
+/// criteria, and the `ItemCollection` only exists once the assets are loaded.
+pub fn load_game(
+    mut commands: Commands,
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+    mut save_games: ResMut<SaveGames>,
+    mut offline: ResMut<OfflineProgress>,
+    mut world_seed: ResMut<WorldSeed>,
+    facility_map: Res<FacilityMap>,
+    mut ui_state: ResMut<UiState>,
+    (mut inspection_log, mut incident_log): (ResMut<InspectionLog>, ResMut<IncidentLog>),
+    mut exploration_map: ResMut<ExplorationMap>,
+    mut robot_commands: ResMut<RobotCommands>,
+    mut pipe_line: ResMut<PipeLine>,
+    information_collection: Res<InformationCollection>,
+    item_collection: Option<Res<ItemCollection>>,
+    items: Res<Assets<LoadedItem>>,
+    existing: Query<Entity, With<ItemName>>,
+) {
+    let item_collection = match item_collection {
+        Some(item_collection) if state.current() == &GameState::Game => item_collection,
+        _ => return,
+    };
+    let (slot, resume) = match save_games.request {
+        Some(SaveRequest::Load(slot)) => (slot, false),
+        Some(SaveRequest::Resume(slot)) => (slot, true),
+        _ => return,
+    };
+    save_games.request = None;
+    let save_game = match SaveGame::load(save_games.path(slot)) {
+        Ok(save_game) => save_game,
+        Err(error) => {
+            log::error!("could not load {}: {}", slot, error);
+            save_games.status = format!("Could not load {}: {}", slot, error);
+            return;
+        }
+    };
+    if save_game.map != facility_map.path {
+        log::warn!(
+            "{} was saved on map {:?}, the current map is {:?}",
+            slot,
+            save_game.map,
+            facility_map.path
+        );
+    }
+
+    existing.for_each(|entity| commands.entity(entity).despawn_recursive());
+    let offset = save_game.play_time - time.seconds_since_startup();
+    save_games.time_offset = offset;
+    save_games.start_restoring();
+    save_games.autosave.reset();
+    // a save loaded during play was not away from anything
+    offline.pending = resume.then(|| save_game.age() as f64);
+    offline.report = None;
+
+    let spawner = ItemSpawner::new(
+        &items,
+        &information_collection,
+        &item_collection,
+        save_game.seed,
+    );
+    pipe_line.root = None;
+    pipe_line.current.clear();
+    pipe_line.finished = true;
+    for root in &save_game.items {
+        let handle = match item_collection.handle(&root.item.item) {
+            Some(handle) => handle,
+            None => {
+                log::warn!("{}: unknown item {}", slot, root.item.item);
+                continue;
+            }
+        };
+        let mut builder = spawner.item(&handle);
+        builder.transform(Transform {
+            translation: root.position,
+            rotation: Quat::from_rotation_z(root.rotation.to_radians()),
+            ..Default::default()
+        });
+        if let Some(seed) = root.item.seed {
+            builder.seed(seed);
+        }
+        let item = known_parts(&root.item, &item_collection);
+        attach_parts(&mut builder, &item.parts, &item_collection);
+        let entity = builder.build(&mut commands);
+        if let Some(autopilot) = root.autopilot {
+            commands.entity(entity).insert(Autopilot {
+                enabled: autopilot.enabled,
+                prefer_cheap_surfaces: autopilot.prefer_cheap_surfaces,
+                ..Default::default()
+            });
+        }
+        if let Some(head) = root.pipe_source {
+            commands.entity(entity).insert(PipeSource { head });
+            pipe_line.root.get_or_insert(entity);
+        }
+        save_games.restoring.push((entity, item));
+    }
+
+    *world_seed = WorldSeed::new(save_game.seed);
+    exploration_map
+        .explored
+        .iter_mut()
+        .for_each(|explored| *explored = false);
+    for &cell in &save_game.explored {
+        if let Some(explored) = exploration_map.explored.get_mut(cell) {
+            *explored = true;
+        }
+    }
+    let statistics = save_game.statistics;
+    ui_state.manometers_inspected = statistics.manometers_inspected;
+    ui_state.hot_spots_inspected = statistics.hot_spots_inspected;
+    ui_state.show_attachment_menu = None;
+    robot_commands.queue.clear();
+    robot_commands.energy_used = statistics.energy_used;
+    incident_log.incidents.clear();
+    incident_log.detected = statistics.detected;
+    incident_log.false_alarms = statistics.false_alarms;
+    incident_log.missed = statistics.missed;
+    inspection_log.readings = statistics.readings;
+    inspection_log
+        .readings
+        .iter_mut()
+        .for_each(|reading| reading.timestamp -= offset);
+    inspection_log.thermal = statistics.thermal;
+    inspection_log
+        .thermal
+        .iter_mut()
+        .for_each(|reading| reading.timestamp -= offset);
+
+    log::info!(
+        "loaded {}: {} items, played {}",
+        slot,
+        save_game.items.len(),
+        format_duration(save_game.play_time)
+    );
+    save_games.status = format!("Loaded {}", slot);
+}
+
+/// The item without the parts that cannot be attached, renamed or removed items for
+/// example, so restoring does not wait for them.
+fn known_parts(saved: &SavedItem, item_collection: &ItemCollection) -> SavedItem {
+    SavedItem {
+        parts: saved
+            .parts
+            .iter()
+            .filter(|part| {
+                let known = part.at.is_some() && item_collection.handle(&part.item).is_some();
+                if !known {
+                    log::warn!("save game: cannot attach {} at {:?}", part.item, part.at);
+                }
+                known
+            })
+            .map(|part| known_parts(part, item_collection))
+            .collect(),
+        ..saved.clone()
+    }
+}
+
+fn attach_parts<'a, 'w>(
+    builder: &'a mut ItemBuilder<'w>,
+    parts: &[SavedItem],
+    item_collection: &ItemCollection,
+) -> &'a mut ItemBuilder<'w> {
+    for part in parts {
+        let (handle, at) = match (item_collection.handle(&part.item), part.at) {
+            (Some(handle), Some(at)) => (handle, at),
+            _ => continue,
+        };
+        builder.attach_then(&handle, at, |child| {
+            if let Some(seed) = part.seed {
+                child.seed(seed);
+            }
+            attach_parts(child, &part.parts, item_collection)
+        });
+    }
+    builder
+}
+
+/// Puts the saved charge, inspection progress, faults, door and valve state back into the
+/// loaded items, each one once it and its parts are attached.
+pub fn restore_item_state(
+    mut commands: Commands,
+    mut save_games: ResMut<SaveGames>,
+    mut items: Query<(
+        &ParentEntity,
+        &AttachmentMap<Attachment>,
+        Option<&mut Battery>,
+        Option<&mut Manometer>,
+        Option<&mut HotSpot>,
+        Option<&mut Door>,
+    )>,
+) {
+    if save_games.restoring.is_empty() {
+        return;
+    }
+    if save_games.restore_frames == 0 {
+        log::warn!(
+            "save game: {} items did not attach, their state is not restored",
+            save_games.restoring.len()
+        );
+        save_games.restoring.clear();
+        return;
+    }
+    save_games.restore_frames -= 1;
+    let offset = save_games.time_offset;
+    let session_time = |timestamp: Option<f64>| timestamp.map(|timestamp| timestamp - offset);
+    for (entity, saved) in std::mem::take(&mut save_games.restoring) {
+        let (parent, attachments, battery, manometer, hot_spot, door) = match items.get_mut(entity)
+        {
+            Ok(item) => item,
+            // despawned before it was restored
+            Err(..) => continue,
+        };
+        let parts = saved
+            .parts
+            .iter()
+            .map(|part| {
+                part.at
+                    .and_then(|aid| attachments.0.get(&aid))
+                    .and_then(|attachment| attachment.attached)
+            })
+            .collect::<Option<Vec<_>>>();
+        let parts = match parts {
+            Some(parts) if *parent != ParentEntity::WaitForAttach => parts,
+            _ => {
+                save_games.restoring.push((entity, saved));
+                continue;
+            }
+        };
+
+        if let (Some(mut battery), Some(charge)) = (battery, saved.charge) {
+            battery.charge = charge.min(battery.capacity);
+        }
+        if let (Some(mut manometer), Some(inspection)) = (manometer, &saved.manometer) {
+            manometer.progress = inspection.progress;
+            manometer.quality = inspection.quality;
+            manometer.reading = inspection.reading;
+            manometer.last_inspected = session_time(inspection.last_inspected);
+        }
+        if let (Some(mut hot_spot), Some(inspection)) = (hot_spot, &saved.hot_spot) {
+            hot_spot.progress = inspection.progress;
+            hot_spot.quality = inspection.quality;
+            hot_spot.reading = inspection.reading;
+            hot_spot.last_inspected = session_time(inspection.last_inspected);
+        }
+        if let (Some(mut door), Some(open)) = (door, saved.open) {
+            door.open = open;
+        }
+        if let Some(open) = saved.valve {
+            commands.entity(entity).insert(Valve { open });
+        }
+        if let Some(fault) = saved.fault {
+            commands.entity(entity).insert(Fault {
+                kind: fault.kind,
+                offset: fault.offset,
+                started: fault.started - offset,
+                deadline: fault.deadline - offset,
+            });
+        }
+        for ((item, _), part) in parts.into_iter().zip(saved.parts) {
+            save_games.restoring.push((item, part));
+        }
+    }
+}
+
+/// Continues the most recent save when the game starts.
+pub fn resume_latest(mut save_games: ResMut<SaveGames>) {
+    if let Some(slot) = save_games.latest() {
+        save_games.request = Some(SaveRequest::Resume(slot));
+    }
+}
+
+pub fn save_games_ui(
+    egui_ctx: ResMut<EguiContext>,
+    mut save_games: ResMut<SaveGames>,
+    mut offline: ResMut<OfflineProgress>,
+) {
+    egui::Window::new("Save Games")
+        .default_pos((20.0, 700.0))
+        .show(egui_ctx.ctx(), |ui| {
+            let now = unix_time();
+            for (slot, summary) in save_games.summaries.clone() {
+                ui.horizontal(|ui| {
+                    ui.label(slot.to_string());
+                    if slot != SaveSlot::Auto && ui.button("Save").clicked() {
+                        save_games.request = Some(SaveRequest::Save(slot));
+                    }
+                    if ui
+                        .add(egui::Button::new("Load").enabled(summary.is_some()))
+                        .clicked()
+                    {
+                        save_games.request = Some(SaveRequest::Load(slot));
+                    }
+                    match summary {
+                        Some(summary) => ui.label(format!(
+                            "{} ago, played {}, {} robots, {} manometers / {} hot spots inspected",
+                            format_duration(now.saturating_sub(summary.saved_at) as f64),
+                            format_duration(summary.play_time),
+                            summary.robots,
+                            summary.manometers_inspected,
+                            summary.hot_spots_inspected
+                        )),
+                        None => ui.label("Empty"),
+                    };
+                });
+            }
+            let mut hours = offline.max_away / 3600.0;
+            ui.add(egui::Slider::new(&mut hours, 0.0..=48.0).text("Offline Hours"));
+            if (hours * 3600.0 - offline.max_away).abs() > f64::EPSILON {
+                offline.max_away = hours * 3600.0;
+            }
+            if !save_games.status.is_empty() {
+                ui.label(save_games.status.clone());
+            }
+        });
+}
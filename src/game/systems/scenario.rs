@@ -0,0 +1,197 @@
+use std::{collections::HashSet, fs};
+
+use bevy::{app::AppExit, log, prelude::*};
+use bevy_rapier2d::{physics::IntoEntity, prelude::*};
+use rand::Rng;
+
+use crate::{
+    game::{
+        builders::item::{ItemBuilder, ItemSpawner},
+        components::robot::*,
+        resources::{
+            compliance::FacilityCompliance,
+            facility_map::{FacilityMap, MapObjectKind},
+            inspection_log::InspectionLog,
+            item_collection::{ItemCollection, LoadedItem},
+            item_information::InformationCollection,
+            robot_commands::RobotCommands,
+            scenario::{Scenario, ScenarioPart, SimMetrics, SimRun},
+            world_seed::WorldSeed,
+        },
+    },
+    utils::world_position,
+    GameState,
+};
+
+/// Replaces the frame time with the simulated clock, runs right after the time system.
+/// The clock stands still while loading, so a run does not depend on how long that took.
+pub fn advance_clock(
+    state: Res<State<GameState>>,
+    mut sim_run: ResMut<SimRun>,
+    mut time: ResMut<Time>,
+) {
+    if state.current() == &GameState::Game {
+        sim_run.tick();
+    }
+    *time = sim_run.clock.clone();
+}
+
+pub fn spawn_scenario(
+    mut commands: Commands,
+    scenario: Res<Scenario>,
+    facility_map: Res<FacilityMap>,
+    mut sim_run: ResMut<SimRun>,
+    information_collection: Res<InformationCollection>,
+    item_collection: Res<ItemCollection>,
+    items: Res<Assets<LoadedItem>>,
+    mut world_seed: ResMut<WorldSeed>,
+) {
+    let spawner = ItemSpawner::new(
+        &items,
+        &information_collection,
+        &item_collection,
+        world_seed.rng.gen(),
+    );
+    let start = facility_map
+        .objects(MapObjectKind::RobotStart)
+        .next()
+        .map(|start| start.position)
+        .unwrap_or_default();
+
+    for robot in &scenario.robots {
+        let body = match item_handle(&item_collection, &robot.body) {
+            Some(body) => body,
+            None => continue,
+        };
+        let mut builder = spawner.item(&body);
+        builder.transform(Transform {
+            translation: robot.position.unwrap_or(start).extend(0.0),
+            rotation: Quat::from_rotation_z(robot.rotation.to_radians()),
+            ..Default::default()
+        });
+        attach_parts(&mut builder, &robot.parts, &item_collection);
+        let entity = builder.build(&mut commands);
+        commands.entity(entity).insert(Autopilot {
+            enabled: robot.autopilot,
+            prefer_cheap_surfaces: robot.prefer_cheap_surfaces,
+            ..Default::default()
+        });
+    }
+    for waypoint in &scenario.waypoints {
+        spawner
+            .item(&item_collection.waypoint_marker)
+            .transform(Transform::from_translation(waypoint.extend(0.0)))
+            .build(&mut commands);
+    }
+    sim_run.started = Some(sim_run.clock.seconds_since_startup());
+    log::info!(
+        "scenario {}: {} robots, {} waypoints",
+        scenario.name,
+        scenario.robots.len(),
+        scenario.waypoints.len()
+    );
+}
+
+fn attach_parts<'a, 'w>(
+    builder: &'a mut ItemBuilder<'w>,
+    parts: &[ScenarioPart],
+    item_collection: &ItemCollection,
+) -> &'a mut ItemBuilder<'w> {
+    for part in parts {
+        if let Some(handle) = item_handle(item_collection, &part.item) {
+            builder.attach_then(&handle, part.at, |child| {
+                attach_parts(child, &part.parts, item_collection)
+            });
+        }
+    }
+    builder
+}
+
+fn item_handle(item_collection: &ItemCollection, name: &str) -> Option<Handle<LoadedItem>> {
+    let handle = item_collection.handle(name);
+    if handle.is_none() {
+        log::warn!("scenario: unknown item {}", name);
+    }
+    handle
+}
+
+/// Sums up the distance driven by each robot and counts contacts with anything that is
+/// not part of the same robot.
+pub fn track_metrics(
+    mut sim_run: ResMut<SimRun>,
+    mut contact_events: EventReader<ContactEvent>,
+    robots: Query<(Entity, &RigidBodyPosition), With<RobotMass>>,
+    parents: Query<&ParentEntity>,
+) {
+    if sim_run.started.is_none() {
+        return;
+    }
+    robots.for_each(|(entity, rb_pos)| {
+        let position = world_position(rb_pos);
+        if let Some(last) = sim_run.positions.insert(entity, position) {
+            sim_run.distance += last.distance(position);
+        }
+    });
+    for contact_event in contact_events.iter() {
+        if let ContactEvent::Started(a, b) = contact_event {
+            let parent = |handle: &ColliderHandle| parents.get(handle.entity()).ok().copied();
+            let robot = |parent: Option<ParentEntity>| {
+                matches!(parent, Some(ParentEntity::Robot(Some(..))))
+            };
+            let (a, b) = (parent(a), parent(b));
+            if (robot(a) || robot(b)) && a != b {
+                sim_run.collisions += 1;
+            }
+        }
+    }
+}
+
+/// Writes the metrics and quits once the scenario ran for its duration.
+pub fn finish(
+    sim_run: Res<SimRun>,
+    scenario: Res<Scenario>,
+    world_seed: Res<WorldSeed>,
+    inspection_log: Res<InspectionLog>,
+    compliance: Res<FacilityCompliance>,
+    robot_commands: Res<RobotCommands>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    if !sim_run.is_finished() {
+        return;
+    }
+    let metrics = SimMetrics {
+        scenario: scenario.name.clone(),
+        seed: world_seed.seed,
+        timestep: sim_run.timestep,
+        duration: sim_run.elapsed(),
+        frames: sim_run.frames,
+        manometers_inspected: inspection_log
+            .readings
+            .iter()
+            .map(|reading| reading.manometer)
+            .collect::<HashSet<_>>()
+            .len(),
+        hot_spots_inspected: inspection_log
+            .thermal
+            .iter()
+            .map(|reading| reading.hot_spot)
+            .collect::<HashSet<_>>()
+            .len(),
+        readings: inspection_log.readings.len(),
+        thermal_readings: inspection_log.thermal.len(),
+        compliance: compliance.fraction(),
+        energy_used: robot_commands.energy_used,
+        distance_driven: sim_run.distance,
+        collisions: sim_run.collisions,
+    };
+    let json = serde_json::to_string_pretty(&metrics).expect("metrics are serializable");
+    match &sim_run.output {
+        Some(path) => {
+            if let Err(error) = fs::write(path, json) {
+                log::error!("can not write metrics to {}: {}", path.display(), error);
+            }
+        }
+        None => println!("{}", json),
+    }
+    app_exit_events.send(AppExit);
+}
@@ -0,0 +1,115 @@
+use bevy::prelude::*;
+use bevy_prototype_debug_lines::DebugLines;
+use bevy_rapier2d::{
+    physics::{IntoEntity, QueryPipelineColliderComponentsQuery, QueryPipelineColliderComponentsSet},
+    prelude::*,
+};
+use rand::Rng;
+
+use crate::{
+    consts::PHYSICS_SCALE,
+    game::{
+        components::robot::*,
+        resources::{
+            robot_commands::*,
+            ui::UiState,
+            world_seed::{SystemRng, WorldSeed},
+        },
+    },
+    utils::world_position,
+};
+
+pub fn range_sensors(
+    query_pipeline: Res<QueryPipeline>,
+    collider_query: QueryPipelineColliderComponentsQuery,
+    colliders: Query<(&ParentEntity, &ColliderType)>,
+    mut sensors: Query<(
+        Entity,
+        &mut RangeSensor,
+        &mut Powered,
+        &RigidBodyPosition,
+        &ParentEntity,
+    )>,
+    mut robot_commands: ResMut<RobotCommands>,
+    world_seed: Res<WorldSeed>,
+    mut rng: Local<SystemRng>,
+) {
+    let collider_set = QueryPipelineColliderComponentsSet(&collider_query);
+    let rng = rng.get(&world_seed, "range sensors");
+    for (entity, mut sensor, mut powered, rb_pos, parent_entity) in sensors.iter_mut() {
+        robot_commands.send(RobotCommand {
+            robot_entity: *parent_entity,
+            command: RobotCommandType::Sense { entity },
+            power_consumption: sensor.power_consumption,
+        });
+        if !powered.0 {
+            sensor.readings.clear();
+            continue;
+        }
+        powered.0 = false;
+
+        // the robots own parts and other sensor volumes are invisible to the rays
+        let filter = |handle: ColliderHandle| match colliders.get(handle.entity()) {
+            Ok((other_parent, collider_type)) => {
+                other_parent != parent_entity && *collider_type == ColliderType::Solid
+            }
+            Err(..) => true,
+        };
+        let origin = Point::from(rb_pos.position.translation.vector);
+        let readings = (0..sensor.rays)
+            .map(|ray| {
+                let dir = rb_pos.position.rotation
+                    * Rotation::from_angle(sensor.ray_angle(ray))
+                    * Vector::y();
+                let hit = query_pipeline
+                    .cast_ray(
+                        &collider_set,
+                        &Ray::new(origin, dir),
+                        sensor.max_range / PHYSICS_SCALE,
+                        true,
+                        InteractionGroups::all(),
+                        Some(&filter),
+                    )
+                    .map(|(_, toi)| toi * PHYSICS_SCALE);
+                // a miss reads exactly max_range, so it is never taken for an obstacle
+                match hit {
+                    Some(distance) if sensor.noise > 0.0 => (distance
+                        + rng.gen_range(-sensor.noise..sensor.noise))
+                    .clamp(0.0, sensor.max_range),
+                    Some(distance) => distance.clamp(0.0, sensor.max_range),
+                    None => sensor.max_range,
+                }
+            })
+            .collect::<Vec<_>>();
+        sensor.readings = readings;
+    }
+}
+
+pub fn draw_sensor_rays(
+    ui_state: Res<UiState>,
+    mut lines: ResMut<DebugLines>,
+    sensors: Query<(&RangeSensor, &RigidBodyPosition)>,
+) {
+    if !ui_state.show_sensor_rays {
+        return;
+    }
+    sensors.for_each(|(sensor, rb_pos)| {
+        let start = world_position(rb_pos);
+        for (ray, distance) in sensor.readings.iter().enumerate() {
+            let dir = rb_pos.position.rotation
+                * Rotation::from_angle(sensor.ray_angle(ray))
+                * Vector::y();
+            let end = start + Vec2::new(dir.x, dir.y) * *distance;
+            lines.line_colored(
+                start.extend(999.),
+                end.extend(999.),
+                0.0,
+                if *distance < sensor.max_range {
+                    Color::ORANGE_RED
+                } else {
+                    Color::rgba(0.0, 1.0, 0.5, 0.3)
+                },
+            );
+        }
+    });
+}
@@ -0,0 +1,137 @@
+use bevy::{prelude::*, render::camera::OrthographicProjection};
+use bevy_egui::EguiContext;
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+
+use crate::{
+    game::{
+        builders::item::ItemSpawner,
+        components::robot::*,
+        resources::{
+            compliance::FacilityCompliance,
+            inspection_log::InspectionLog,
+            item_collection::{ItemCollection, LoadedItem},
+            item_information::InformationCollection,
+            robot_commands::*,
+            world_seed::{SystemRng, WorldSeed},
+        },
+    },
+    utils::{cursor_world_position, world_position},
+};
+
+const PLACE_SNAP: f32 = 8.0;
+const DOOR_POWER: f32 = 50.0;
+
+/// `1`, `2` and `3` place a wall, door or gate at the cursor, rotated upright while
+/// `Shift` is held.
+pub fn place_structures(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    egui_ctx: Res<EguiContext>,
+    information_collection: Res<InformationCollection>,
+    item_collection: Res<ItemCollection>,
+    items: Res<Assets<LoadedItem>>,
+    world_seed: Res<WorldSeed>,
+    mut rng: Local<SystemRng>,
+    q_camera: Query<(&Transform, &OrthographicProjection), With<Camera>>,
+) {
+    let handle = if keyboard_input.just_pressed(KeyCode::Key1) {
+        &item_collection.wall
+    } else if keyboard_input.just_pressed(KeyCode::Key2) {
+        &item_collection.door
+    } else if keyboard_input.just_pressed(KeyCode::Key3) {
+        &item_collection.gate
+    } else {
+        return;
+    };
+    if egui_ctx.ctx().wants_keyboard_input() {
+        return;
+    }
+    let win = windows.get_primary().expect("no primary window");
+    let (camera, projection) = match q_camera.single() {
+        Ok(camera) => camera,
+        Err(..) => return,
+    };
+    if let Some(cursor) = cursor_world_position(win, camera, projection) {
+        let position = (cursor / PLACE_SNAP).round() * PLACE_SNAP;
+        let upright =
+            keyboard_input.pressed(KeyCode::LShift) || keyboard_input.pressed(KeyCode::RShift);
+        let rotation = if upright {
+            Quat::from_rotation_z(std::f32::consts::FRAC_PI_2)
+        } else {
+            Quat::IDENTITY
+        };
+        let seed = rng.get(&world_seed, "structures").gen();
+        ItemSpawner::new(&items, &information_collection, &item_collection, seed)
+            .item(handle)
+            .transform(Transform {
+                translation: position.extend(0.0),
+                rotation,
+                ..Default::default()
+            })
+            .build(&mut commands);
+    }
+}
+
+/// `F` toggles the nearest door within reach of each robot. Robots on autopilot open
+/// closed doors they can pass.
+pub fn operate_doors(
+    keyboard_input: Res<Input<KeyCode>>,
+    compliance: Res<FacilityCompliance>,
+    inspection_log: Res<InspectionLog>,
+    robots: Query<(&ParentEntity, &Autopilot, &RigidBodyPosition)>,
+    doors: Query<(Entity, &Door, &RigidBodyPosition)>,
+    mut robot_commands: ResMut<RobotCommands>,
+) {
+    let toggle = keyboard_input.just_pressed(KeyCode::F);
+    let (fraction, inspections) = (compliance.fraction(), inspection_log.count());
+    robots.for_each(|(parent_entity, autopilot, rb_pos)| {
+        let position = world_position(rb_pos);
+        let nearest = doors
+            .iter()
+            .map(|(entity, door, door_pos)| {
+                (entity, door, world_position(door_pos).distance(position))
+            })
+            .filter(|(_, door, distance)| *distance <= door.reach)
+            .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal));
+        let (entity, door) = match nearest {
+            Some((entity, door, _)) => (entity, door),
+            None => return,
+        };
+        let open = if toggle {
+            !door.open
+        } else if autopilot.enabled && !door.open && door.allows(fraction, inspections) {
+            true
+        } else {
+            return;
+        };
+        robot_commands.send(RobotCommand {
+            robot_entity: *parent_entity,
+            command: RobotCommandType::OperateDoor { door: entity, open },
+            power_consumption: DOOR_POWER,
+        });
+    });
+}
+
+/// Open doors stop colliding and show their open frame, denied gates are tinted.
+pub fn update_doors(
+    query: Query<
+        (&Door, &mut ColliderType, &mut TextureAtlasSprite),
+        Or<(Changed<Door>, Changed<ParentEntity>)>,
+    >,
+) {
+    query.for_each_mut(|(door, mut collider_type, mut sprite)| {
+        *collider_type = if door.open {
+            ColliderType::Sensor
+        } else {
+            ColliderType::Solid
+        };
+        sprite.index = door.open as u32;
+        sprite.color = if door.denied {
+            Color::rgb(1.0, 0.5, 0.5)
+        } else {
+            Color::WHITE
+        };
+    });
+}
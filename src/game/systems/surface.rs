@@ -0,0 +1,35 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    game::{
+        components::robot::*,
+        resources::{
+            facility_map::FacilityMap, surface_map::SurfaceMap, terrain_collider::TerrainEditor,
+        },
+    },
+    utils::world_position,
+};
+
+pub fn update_surface_map(
+    mut surface_map: ResMut<SurfaceMap>,
+    facility_map: Res<FacilityMap>,
+    terrain_editor: Res<TerrainEditor>,
+) {
+    if facility_map.is_changed() || terrain_editor.is_changed() {
+        surface_map.rebuild(&facility_map, &terrain_editor.shapes);
+    }
+}
+
+/// Looks up the surface under each track or wheel.
+pub fn update_traction(
+    surface_map: Res<SurfaceMap>,
+    tracks: Query<(&mut Traction, &RigidBodyPosition)>,
+) {
+    tracks.for_each_mut(|(mut traction, rb_pos)| {
+        let surface = surface_map.surface_at(world_position(rb_pos));
+        if traction.surface != surface {
+            *traction = Traction::new(surface);
+        }
+    });
+}
@@ -1,63 +1,216 @@
-use bevy::{log, prelude::*};
+use bevy::{log, prelude::*, render::camera::OrthographicProjection};
 
+use bevy_egui::EguiContext;
 use bevy_prototype_debug_lines::DebugLines;
 use bevy_rapier2d::prelude::*;
-use itertools::Itertools;
-
-use crate::game::resources::terrain_collider::TerrainCollider;
-
-pub fn spawn(mut commands: Commands) {
-    // commands
-    //     .spawn_bundle(RigidBodyBundle {
-    //         body_type: RigidBodyType::Static,
-    //         ..Default::default()
-    //     })
-    //     .insert_bundle(ColliderBundle {
-    //         shape: ColliderShape::polyline(vec![Vec2::X.into(), Vec2::Y.into()], None),
-    //         ..Default::default()
-    //     })
-    //     .insert(TerrainCollider::default());
+
+use crate::{
+    game::resources::{
+        facility_map::FacilityMap,
+        terrain_collider::{TerrainCollider, TerrainEditor},
+    },
+    utils::cursor_world_position,
+};
+
+// pick radius in screen pixels
+const PICK_RADIUS: f32 = 8.0;
+const VERTEX_SIZE: f32 = 3.0;
+
+pub fn spawn(mut terrain_editor: ResMut<TerrainEditor>, facility_map: Res<FacilityMap>) {
+    let path = facility_map.terrain_path();
+    if path.exists() {
+        match terrain_editor.load(&path) {
+            Ok(()) => terrain_editor.history.clear(),
+            Err(error) => log::warn!("can not load terrain {}: {}", path.display(), error),
+        }
+    }
+}
+
+/// Keeps one static body per shape in line with the editor.
+pub fn sync(
+    mut commands: Commands,
+    mut terrain_editor: ResMut<TerrainEditor>,
+    mut colliders: Query<(Entity, &mut TerrainCollider)>,
+) {
+    if !terrain_editor.changed {
+        return;
+    }
+    terrain_editor.changed = false;
+
+    let mut missing = (0..terrain_editor.shapes.len()).collect::<Vec<_>>();
+    colliders.for_each_mut(|(entity, mut terrain_collider)| {
+        match terrain_editor.shapes.get(terrain_collider.index) {
+            Some(shape) => {
+                if terrain_collider.vertices != shape.vertices
+                    || terrain_collider.closed != shape.closed
+                    || terrain_collider.zone != shape.is_zone()
+                {
+                    terrain_collider.vertices = shape.vertices.clone();
+                    terrain_collider.closed = shape.closed;
+                    terrain_collider.zone = shape.is_zone();
+                }
+                missing.retain(|index| *index != terrain_collider.index);
+            }
+            None => commands.entity(entity).despawn(),
+        }
+    });
+    for index in missing {
+        let shape = &terrain_editor.shapes[index];
+        commands
+            .spawn_bundle(RigidBodyBundle {
+                body_type: RigidBodyType::Static,
+                ..Default::default()
+            })
+            .insert_bundle(ColliderBundle {
+                shape: ColliderShape::ball(0.1),
+                collider_type: ColliderType::Sensor,
+                ..Default::default()
+            })
+            .insert(TerrainCollider {
+                index,
+                vertices: shape.vertices.clone(),
+                closed: shape.closed,
+                zone: shape.is_zone(),
+            });
+    }
 }
 
 pub fn build(
-    query: Query<(&mut ColliderShape, &TerrainCollider), Changed<TerrainCollider>>,
-    mut lines: ResMut<DebugLines>,
+    query: Query<
+        (&mut ColliderShape, &mut ColliderType, &TerrainCollider),
+        Changed<TerrainCollider>,
+    >,
     rapier_config: Res<RapierConfiguration>,
 ) {
-    query.for_each_mut(|(mut collider, terrain_collider)| {
-        log::info!("build_terrain");
-        let vertices = terrain_collider.vertices.clone();
-        // Spawn terrain
-        vertices.iter().tuple_windows().for_each(|(a, b)| {
-            lines.line(Vec3::new(a.x, a.y, 0.0), Vec3::new(b.x, b.y, 0.0), 100.0);
-        });
+    query.for_each_mut(|(mut collider, mut collider_type, terrain_collider)| {
+        let vertices = &terrain_collider.vertices;
         if vertices.len() >= 2 {
+            let indices = if terrain_collider.closed && vertices.len() > 2 {
+                let count = vertices.len() as u32;
+                Some((0..count).map(|i| [i, (i + 1) % count]).collect())
+            } else {
+                None
+            };
             *collider = ColliderShape::polyline(
                 vertices
                     .iter()
                     .map(|v| (*v / rapier_config.scale).into())
                     .collect(),
-                None,
-            )
+                indices,
+            );
+            *collider_type = if terrain_collider.zone {
+                ColliderType::Sensor
+            } else {
+                ColliderType::Solid
+            };
+        } else {
+            // a single vertex has nothing to collide with
+            *collider_type = ColliderType::Sensor;
         }
     });
 }
 
+pub fn draw(terrain_editor: Res<TerrainEditor>, mut lines: ResMut<DebugLines>) {
+    for (s, shape) in terrain_editor.shapes.iter().enumerate() {
+        let color = if terrain_editor.enabled && terrain_editor.active == Some(s) {
+            Color::YELLOW
+        } else if shape.is_zone() {
+            Color::GREEN
+        } else {
+            Color::WHITE
+        };
+        for (_, a, b) in shape.segments() {
+            lines.line_colored(a.extend(0.0), b.extend(0.0), 0.0, color);
+        }
+        if terrain_editor.enabled {
+            for (v, vertex) in shape.vertices.iter().enumerate() {
+                let color = if terrain_editor.selected == Some((s, v)) {
+                    Color::RED
+                } else {
+                    color
+                };
+                for offset in [Vec2::new(VERTEX_SIZE, 0.0), Vec2::new(0.0, VERTEX_SIZE)] {
+                    lines.line_colored(
+                        (*vertex - offset).extend(0.0),
+                        (*vertex + offset).extend(0.0),
+                        0.0,
+                        color,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Right click appends a vertex, left drag moves one. `N` starts a new shape, `C` closes
+/// it, `I` inserts a vertex into the nearest segment, `Delete` removes the selected vertex
+/// and `Ctrl+Z` undoes the last edit.
 pub fn update(
-    query: Query<&mut TerrainCollider>,
+    mut terrain_editor: ResMut<TerrainEditor>,
     mouse_button_input: Res<Input<MouseButton>>,
+    keyboard_input: Res<Input<KeyCode>>,
     windows: Res<Windows>,
+    q_camera: Query<(&Transform, &OrthographicProjection), With<Camera>>,
+    egui_ctx: Res<EguiContext>,
 ) {
-    if mouse_button_input.pressed(MouseButton::Right) {
-        query.for_each_mut(|mut terrain_collider| {
-            log::info!("update_terrain");
-            let win = windows.get_primary().expect("no primary window");
-            let offset = Vec2::new(win.width(), win.height()) / 2.;
-            if let Some(mouse_pos) = win.cursor_position() {
-                terrain_collider
-                    .vertices
-                    .push(mouse_pos.extend(1.0).truncate() - offset);
-            }
-        });
+    if !terrain_editor.enabled {
+        return;
+    }
+    let control =
+        keyboard_input.pressed(KeyCode::LControl) || keyboard_input.pressed(KeyCode::RControl);
+    if control && keyboard_input.just_pressed(KeyCode::Z) {
+        terrain_editor.undo();
+    }
+    if keyboard_input.just_pressed(KeyCode::N) {
+        terrain_editor.active = None;
+        terrain_editor.selected = None;
+    }
+    if keyboard_input.just_pressed(KeyCode::C) {
+        terrain_editor.toggle_closed();
+    }
+    if keyboard_input.just_pressed(KeyCode::Delete) || keyboard_input.just_pressed(KeyCode::Back) {
+        terrain_editor.delete_selected();
+    }
+
+    if mouse_button_input.just_released(MouseButton::Left) {
+        terrain_editor.dragging = false;
+    }
+    if egui_ctx.ctx().wants_pointer_input() || egui_ctx.ctx().is_pointer_over_area() {
+        return;
+    }
+    let win = windows.get_primary().expect("no primary window");
+    let (camera, projection) = match q_camera.single() {
+        Ok(camera) => camera,
+        Err(..) => return,
+    };
+    let cursor = match cursor_world_position(win, camera, projection) {
+        Some(cursor) => cursor,
+        None => return,
+    };
+    let radius = PICK_RADIUS * projection.scale;
+    let snapped = terrain_editor.snapped(cursor);
+
+    if keyboard_input.just_pressed(KeyCode::I) {
+        terrain_editor.insert_vertex(snapped, radius);
+    }
+    if mouse_button_input.just_pressed(MouseButton::Right) {
+        // clicking the first vertex of the active shape closes it
+        let first = terrain_editor.active.map(|active| (active, 0));
+        if first.is_some() && terrain_editor.vertex_at(cursor, radius) == first {
+            terrain_editor.toggle_closed();
+        } else {
+            terrain_editor.add_vertex(snapped);
+        }
+    }
+    if mouse_button_input.just_pressed(MouseButton::Left) {
+        terrain_editor.selected = terrain_editor.vertex_at(cursor, radius);
+        if let Some((shape, _)) = terrain_editor.selected {
+            terrain_editor.active = Some(shape);
+            terrain_editor.dragging = true;
+            terrain_editor.drag_saved = false;
+        }
+    }
+    if terrain_editor.dragging && mouse_button_input.pressed(MouseButton::Left) {
+        terrain_editor.move_selected(snapped);
     }
 }
@@ -1,76 +1,222 @@
-use bevy::prelude::*;
+use bevy::{log, prelude::*};
 use bevy_ecs_tilemap::prelude::*;
-use rand::{thread_rng, Rng};
 
-use crate::game::resources::{item_collection::ItemCollection, item_information::InformationCollection};
+use crate::game::{
+    builders::item::ItemSpawner,
+    components::robot::*,
+    resources::{
+        facility_map::{FacilityMap, MapObject, MapObjectKind},
+        item_collection::{ItemCollection, LoadedItem},
+        item_information::InformationCollection,
+        pipe_line::PipeLine,
+        world_seed::WorldSeed,
+    },
+    systems::inspection::attach_manometer,
+};
+use rand::Rng;
+
+const CHUNK_SIZE: u32 = 8;
+/// How close the ends of two map pipes need to be to connect, in grid cells.
+const CONNECT_DISTANCE: f32 = 0.5;
 
 pub fn startup(
     mut commands: Commands,
     mut map_query: MapQuery,
-    information_collection: Res<InformationCollection>,
-    item_collection: Res<ItemCollection>,
+    facility_map: Res<FacilityMap>,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    let information = information_collection
-        .get(&item_collection.gras_materials)
-        .unwrap();
+    if facility_map.layers.is_empty() {
+        return;
+    }
 
     // Create map entity and component:
     let map_entity = commands.spawn().id();
     let mut map = Map::new(0u16, map_entity);
 
-    let map_settings = LayerSettings::new(
-        UVec2::new(2, 2),
-        UVec2::new(8, 8),
-        Vec2::new(48.0, 48.0),
-        Vec2::new(information.sprite.size.0, information.sprite.size.1),
-    );
-
-    // Layer 0
-    let (mut layer_0, layer_0_entity) =
-        LayerBuilder::new(&mut commands, map_settings.clone(), 0u16, 0u16);
-
-    // Required to keep track of layers for a map internally.
-    map.add_layer(&mut commands, 0u16, layer_0_entity);
-
-    layer_0.set_all(TileBundle::default());
-
-    map_query.build_layer(&mut commands, layer_0, information.color_material.clone());
-
-    // Make 2 layers on "top" of the base map.
-    for z in 0..2 {
-        let mut new_settings = map_settings.clone();
-        new_settings.set_layer_id(z + 1);
+    for (layer_id, layer) in facility_map.layers.iter().enumerate() {
+        let tileset = match &layer.tileset {
+            Some(tileset) => tileset,
+            None => continue,
+        };
+        let layer_id = layer_id as u16;
+        let mut settings = LayerSettings::new(
+            UVec2::new(
+                (facility_map.size.x + CHUNK_SIZE - 1) / CHUNK_SIZE,
+                (facility_map.size.y + CHUNK_SIZE - 1) / CHUNK_SIZE,
+            ),
+            UVec2::new(CHUNK_SIZE, CHUNK_SIZE),
+            Vec2::new(facility_map.grid_size, facility_map.grid_size),
+            tileset.size,
+        );
+        settings.set_layer_id(layer_id);
         let (mut layer_builder, layer_entity) =
-            LayerBuilder::new(&mut commands, new_settings, 0u16, z + 1);
-
-        let mut random = thread_rng();
+            LayerBuilder::new(&mut commands, settings, 0u16, layer_id);
 
-        for _ in 0..100 {
-            let position = UVec2::new(random.gen_range(0..16), random.gen_range(0..16));
-            // Ignore errors for demo sake.
-            let _ = layer_builder.set_tile(
-                position,
-                TileBundle {
-                    tile: Tile {
-                        texture_index: z + 1,
-                        ..Default::default()
-                    },
+        for tile in &layer.tiles {
+            let bundle = TileBundle {
+                tile: Tile {
+                    texture_index: tile.index,
                     ..Default::default()
                 },
-            );
+                ..Default::default()
+            };
+            if layer_builder.set_tile(tile.position, bundle).is_err() {
+                log::warn!(
+                    "map layer {}: tile {} out of bounds",
+                    layer.name,
+                    tile.position
+                );
+            }
         }
 
-        map_query.build_layer(&mut commands, layer_builder, information.color_material.clone());
+        let material = materials.add(ColorMaterial::texture(
+            asset_server.load(tileset.path.as_path()),
+        ));
+        map_query.build_layer(&mut commands, layer_builder, material);
 
         // Required to keep track of layers for a map internally.
-        map.add_layer(&mut commands, 0u16, layer_entity);
+        map.add_layer(&mut commands, layer_id, layer_entity);
     }
 
     // Spawn Map
     // Required in order to use map_query to retrieve layers/tiles.
+    let height = facility_map.size.y as f32 * facility_map.grid_size;
     commands
         .entity(map_entity)
         .insert(map)
-        .insert(Transform::from_xyz(-128.0, -128.0, 0.0))
+        .insert(Transform::from_xyz(
+            facility_map.origin.x,
+            facility_map.origin.y - height,
+            0.0,
+        ))
         .insert(GlobalTransform::default());
 }
+
+/// Spawns the objects of a hand-authored map, the pipe line is not grown in that case.
+/// A pipe whose `Previous` end lies at the `Next` end of another is attached to it, the
+/// first pipe of each line is a source.
+pub fn spawn_objects(
+    mut commands: Commands,
+    facility_map: Res<FacilityMap>,
+    mut pipe_line: ResMut<PipeLine>,
+    mut world_seed: ResMut<WorldSeed>,
+    information_collection: Res<InformationCollection>,
+    item_collection: Res<ItemCollection>,
+    items: Res<Assets<LoadedItem>>,
+) {
+    if !facility_map.is_loaded() {
+        return;
+    }
+    let spawner = ItemSpawner::new(
+        &items,
+        &information_collection,
+        &item_collection,
+        world_seed.rng.gen(),
+    );
+    let rng = &mut world_seed.rng;
+
+    let mut pipes = vec![];
+    for object in &facility_map.objects {
+        let handle = match object.kind {
+            MapObjectKind::Pipe | MapObjectKind::Manometer => {
+                pipes.push(object);
+                continue;
+            }
+            MapObjectKind::Wall => &item_collection.wall,
+            MapObjectKind::Door => &item_collection.door,
+            MapObjectKind::Gate => &item_collection.gate,
+            MapObjectKind::ChargingStation => &item_collection.charging_station,
+            // robots are spawned with the other entities
+            MapObjectKind::RobotStart => continue,
+        };
+        spawner
+            .item(handle)
+            .transform(object.transform())
+            .seed(rng.gen())
+            .build(&mut commands);
+    }
+
+    let downstream = pipe_connections(&pipes, &facility_map, &items, &item_collection);
+    let has_upstream = (0..pipes.len())
+        .map(|i| downstream.contains(&Some(i)))
+        .collect::<Vec<_>>();
+    let mut spawned = vec![false; pipes.len()];
+    let mut roots = vec![];
+    // lines start at pipes nothing flows into, a closed loop starts anywhere
+    let starts = (0..pipes.len())
+        .filter(|&i| !has_upstream[i])
+        .chain(0..pipes.len())
+        .collect::<Vec<_>>();
+    for start in starts {
+        let mut next = Some(start);
+        let mut parent = None;
+        while let Some(i) = next.filter(|&i| !spawned[i]) {
+            spawned[i] = true;
+            let object = pipes[i];
+            let mut builder = match parent {
+                Some(parent) => {
+                    spawner.attachment(&item_collection.gray_pipe, AttachmentPointId::Next, parent)
+                }
+                None => spawner.item(&item_collection.gray_pipe),
+            };
+            builder.transform(object.transform()).seed(rng.gen());
+            if object.kind == MapObjectKind::Manometer {
+                attach_manometer(&mut builder, &item_collection);
+                pipe_line.report.manometers += 1;
+            }
+            let entity = builder.build(&mut commands);
+            if parent.is_none() {
+                commands.entity(entity).insert(PipeSource { head: 1.0 });
+                roots.push(entity);
+            }
+            pipe_line.report.straight += 1;
+            parent = Some(entity);
+            next = downstream[i];
+        }
+    }
+
+    pipe_line.root = roots.first().copied();
+    pipe_line.current.clear();
+    pipe_line.finished = true;
+}
+
+/// The pipe attached to the `Next` end of each pipe. Each pipe is attached to one other at
+/// most, the first one that reaches it.
+fn pipe_connections(
+    pipes: &[&MapObject],
+    facility_map: &FacilityMap,
+    items: &Assets<LoadedItem>,
+    item_collection: &ItemCollection,
+) -> Vec<Option<usize>> {
+    let item = match items.get(&item_collection.gray_pipe) {
+        Some(item) => item,
+        None => return vec![None; pipes.len()],
+    };
+    let endpoint = |object: &MapObject, aid: AttachmentPointId| {
+        item.attachment_points.0.get(&aid).map(|ap| {
+            let transform = object.transform();
+            (transform.translation
+                + transform.rotation * Vec3::new(ap.position.0, ap.position.1, 0.0))
+            .truncate()
+        })
+    };
+    let reach = facility_map.grid_size * CONNECT_DISTANCE;
+
+    let mut taken = vec![false; pipes.len()];
+    pipes
+        .iter()
+        .enumerate()
+        .map(|(i, pipe)| {
+            let next = endpoint(*pipe, AttachmentPointId::Next)?;
+            let j = (0..pipes.len()).find(|&j| {
+                j != i
+                    && !taken[j]
+                    && endpoint(pipes[j], AttachmentPointId::Previous)
+                        .map_or(false, |previous| previous.distance(next) <= reach)
+            })?;
+            taken[j] = true;
+            Some(j)
+        })
+        .collect()
+}
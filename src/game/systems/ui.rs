@@ -1,14 +1,30 @@
 use crate::game::{
     builders::item::{ItemBuilder, ItemSpawner},
     components::robot::*,
-    resources::{item_collection::*, item_information::InformationCollection, ui::UiState},
+    resources::{
+        compliance::FacilityCompliance,
+        exploration_map::ExplorationMap,
+        facility_map::FacilityMap,
+        incident_log::{AlarmResponse, IncidentKind, IncidentLog},
+        inspection_log::{InspectionLog, ReportFormat},
+        item_collection::*,
+        item_information::InformationCollection,
+        pipe_line::PipeLine,
+        pipe_network::PipeNetwork,
+        terrain_collider::TerrainEditor,
+        ui::UiState,
+        world_seed::{SystemRng, WorldSeed},
+    },
+    types::SurfaceType,
 };
+use super::inspection::MIN_QUALITY;
 use bevy::{log, prelude::*};
 use bevy_egui::{
     egui::{self, Color32, FontDefinitions, FontFamily},
     EguiContext, EguiSettings,
 };
 use bevy_rapier2d::prelude::RigidBodyPosition;
+use rand::Rng;
 
 pub fn load_assets(egui_context: ResMut<EguiContext>, _assets: Res<AssetServer>) {
     let mut fonts = FontDefinitions::default();
@@ -61,7 +77,15 @@ pub fn robot_config_ui(
         &Transform,
         &RigidBodyPosition,
     )>,
+    mut autopilots: Query<(&ItemName, &mut Autopilot, &mut PoseEstimate, Option<&RobotMass>)>,
+    range_sensors: Query<(&ItemName, &RangeSensor)>,
+    gas_sensors: Query<(&ItemName, &GasSensor)>,
+    lenses: Query<(&ItemName, &InspectionQuality)>,
     items: Res<Assets<LoadedItem>>,
+    exploration_map: Res<ExplorationMap>,
+    inspection_log: Res<InspectionLog>,
+    compliance: Res<FacilityCompliance>,
+    (world_seed, mut rng, pipe_line): (Res<WorldSeed>, Local<SystemRng>, Res<PipeLine>),
     mut ui_state: ResMut<UiState>,
     mut commands: Commands,
     egui_ctx: ResMut<EguiContext>,
@@ -88,8 +112,9 @@ pub fn robot_config_ui(
             }
             if ui_state.show_attachment_points {
                 if let Some(attachment_menu) = &ui_state.show_attachment_menu {
+                    let seed = rng.get(&world_seed, "attachments").gen();
                     let mut spawner =
-                        ItemSpawner::new(&items, &information_collection, &item_collection);
+                        ItemSpawner::new(&items, &information_collection, &item_collection, seed);
                     ui.separator();
                     if let Some(entity) = attachment_menu.item_to_attach_to.entity {
                         if let Ok((ref mut attachments, transform, rb_pos)) =
@@ -160,7 +185,362 @@ pub fn robot_config_ui(
                 }
             }
             ui.separator();
+            ui.heading("Robots");
+            if ui
+                .button(format!(
+                    "{} Pose Estimate",
+                    if !ui_state.show_pose_estimate {
+                        "Show"
+                    } else {
+                        "Hide"
+                    }
+                ))
+                .clicked()
+            {
+                ui_state.show_pose_estimate = !ui_state.show_pose_estimate;
+            }
+            for (name, mut autopilot, mut estimate, mass) in autopilots.iter_mut() {
+                ui.checkbox(&mut autopilot.enabled, format!("{} Autopilot", name.0));
+                ui.checkbox(&mut autopilot.prefer_cheap_surfaces, "Prefer Cheap Surfaces");
+                ui.checkbox(&mut estimate.use_estimate, "Navigate by Odometry");
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "Estimate: ({:.0}, {:.0})",
+                        estimate.position.x, estimate.position.y
+                    ));
+                    if ui.button("Reset").clicked() {
+                        estimate.initialized = false;
+                    }
+                });
+                if let Some(mass) = mass {
+                    let text = format!(
+                        "Mass: {:.1} kg, Payload: {:.1} / {:.1} kg",
+                        mass.total, mass.payload, mass.max_payload
+                    );
+                    if mass.is_overloaded() {
+                        ui.colored_label(Color32::RED, format!("{} (overloaded)", text));
+                    } else {
+                        ui.label(text);
+                    }
+                }
+            }
+            ui.separator();
+            ui.heading("Telemetry");
+            if ui
+                .button(format!(
+                    "{} Sensor Rays",
+                    if !ui_state.show_sensor_rays {
+                        "Show"
+                    } else {
+                        "Hide"
+                    }
+                ))
+                .clicked()
+            {
+                ui_state.show_sensor_rays = !ui_state.show_sensor_rays;
+            }
+            for (name, sensor) in range_sensors.iter() {
+                ui.label(match sensor.nearest() {
+                    Some((angle, distance)) => format!(
+                        "{}: {:.1} at {:.0}°",
+                        name.0,
+                        distance,
+                        angle.to_degrees()
+                    ),
+                    None if sensor.readings.is_empty() => format!("{}: no power", name.0),
+                    None => format!("{}: clear", name.0),
+                });
+            }
+            if ui
+                .button(format!(
+                    "{} Gas Heatmap",
+                    if !ui_state.show_gas_heatmap {
+                        "Show"
+                    } else {
+                        "Hide"
+                    }
+                ))
+                .clicked()
+            {
+                ui_state.show_gas_heatmap = !ui_state.show_gas_heatmap;
+            }
+            for (name, sensor) in gas_sensors.iter() {
+                ui.label(format!("{}: {:.2} gas", name.0, sensor.reading));
+            }
+            for (name, inspection_quality) in lenses.iter() {
+                if inspection_quality.target.is_some() {
+                    ui.colored_label(
+                        if inspection_quality.quality >= MIN_QUALITY {
+                            Color32::GREEN
+                        } else {
+                            Color32::RED
+                        },
+                        format!(
+                            "{}: {:.0}% quality",
+                            name.0,
+                            inspection_quality.quality * 100.0
+                        ),
+                    );
+                    ui.label(format!(
+                        "focus {:.2} detail {:.2} blur {:.2} angle {:.2}",
+                        inspection_quality.focus,
+                        inspection_quality.detail,
+                        inspection_quality.blur,
+                        inspection_quality.angle
+                    ));
+                } else if inspection_quality.blocked > 0 {
+                    ui.colored_label(Color32::RED, format!("{}: view blocked", name.0));
+                } else {
+                    ui.label(format!("{}: no target", name.0));
+                }
+            }
+            ui.separator();
             ui.heading("Inspections");
-            ui.label(format!("Manometers: {}", ui_state.manometers_inspected));
+            ui.label(format!("Seed: {}", world_seed.seed));
+            let report = &pipe_line.report;
+            ui.label(format!(
+                "Pipes: {} segments{} ({} capped)",
+                report.segments(),
+                if pipe_line.finished { "" } else { ", growing" },
+                report.capped
+            ));
+            ui.label(format!(
+                "Manometers {} / Hot Spots {} / Leaks {} / Valves {}",
+                report.manometers, report.hot_spots, report.leaks, report.valves
+            ));
+            ui.label(format!(
+                "Explored: {:.1}%",
+                exploration_map.explored_fraction() * 100.0
+            ));
+            ui.label(format!(
+                "Inspections: {} manometers / {} hot spots",
+                ui_state.manometers_inspected, ui_state.hot_spots_inspected
+            ));
+            ui.colored_label(
+                if compliance.overdue > 0 {
+                    Color32::RED
+                } else if compliance.due > 0 {
+                    Color32::YELLOW
+                } else {
+                    Color32::GREEN
+                },
+                format!(
+                    "Compliance: {:.0}% of {} targets",
+                    compliance.fraction() * 100.0,
+                    compliance.targets
+                ),
+            );
+            ui.label(format!(
+                "Uninspected {} / Due {} / Overdue {}",
+                compliance.uninspected, compliance.due, compliance.overdue
+            ));
+            if let Some(reading) = inspection_log.last() {
+                ui.colored_label(
+                    if reading.in_band {
+                        Color32::GREEN
+                    } else {
+                        Color32::RED
+                    },
+                    format!(
+                        "Last Reading: {:.2} {} ({:.0}% quality)",
+                        reading.value,
+                        reading.unit,
+                        reading.quality * 100.0
+                    ),
+                );
+            }
+            if let Some(reading) = inspection_log.thermal.last() {
+                ui.colored_label(
+                    if reading.overheated {
+                        Color32::RED
+                    } else {
+                        Color32::GREEN
+                    },
+                    format!(
+                        "Last Thermal: {:.1} °C ({:.0}% quality)",
+                        reading.value,
+                        reading.quality * 100.0
+                    ),
+                );
+            }
+            ui.horizontal(|ui| {
+                for format in [ReportFormat::Csv, ReportFormat::Json] {
+                    let extension = format.extension();
+                    if ui
+                        .button(format!("Export {}", extension.to_uppercase()))
+                        .clicked()
+                    {
+                        let path = format!("reports/inspections.{}", extension);
+                        ui_state.report_status = match inspection_log.export(&path) {
+                            Ok(()) => format!(
+                                "Saved {} readings to {}",
+                                inspection_log.count(),
+                                path
+                            ),
+                            Err(err) => format!("Export failed: {}", err),
+                        };
+                        log::info!("{}", ui_state.report_status);
+                    }
+                }
+            });
+            if !ui_state.report_status.is_empty() {
+                ui.label(ui_state.report_status.clone());
+            }
+        });
+}
+
+pub fn incident_log_ui(egui_ctx: ResMut<EguiContext>, mut incident_log: ResMut<IncidentLog>) {
+    egui::Window::new("Incidents")
+        .default_pos((1000.0, 20.0))
+        .show(egui_ctx.ctx(), |ui| {
+            ui.label(format!(
+                "Detected {} / Missed {} / False Alarms {}",
+                incident_log.detected, incident_log.missed, incident_log.false_alarms
+            ));
+            ui.label(format!("Score: {}", incident_log.score()));
+            ui.horizontal(|ui| {
+                ui.label("Response:");
+                ui.radio_value(&mut incident_log.response, AlarmResponse::None, "None");
+                ui.radio_value(
+                    &mut incident_log.response,
+                    AlarmResponse::Reinspect,
+                    "Re-inspect",
+                );
+                ui.radio_value(
+                    &mut incident_log.response,
+                    AlarmResponse::MarkForRepair,
+                    "Mark for Repair",
+                );
+            });
+            ui.separator();
+            for incident in incident_log.incidents.iter().rev().take(10) {
+                let (color, text) = match incident.kind {
+                    IncidentKind::Detected(kind) => (Color32::GREEN, format!("{} detected", kind)),
+                    IncidentKind::FalseAlarm => (Color32::YELLOW, "False alarm".to_string()),
+                    IncidentKind::Missed(kind) => (Color32::RED, format!("{} missed", kind)),
+                };
+                let value = incident
+                    .value
+                    .map(|value| format!(" at {:.2} {}", value, incident.unit))
+                    .unwrap_or_default();
+                ui.colored_label(
+                    color,
+                    format!(
+                        "[{:.0}s] Manometer {}: {}{}",
+                        incident.timestamp, incident.manometer, text, value
+                    ),
+                );
+            }
+        });
+}
+
+pub fn pipe_network_ui(
+    egui_ctx: ResMut<EguiContext>,
+    network: Res<PipeNetwork>,
+    mut valves: Query<(Entity, &mut Valve)>,
+) {
+    egui::Window::new("Pipe Network")
+        .default_pos((1000.0, 300.0))
+        .show(egui_ctx.ctx(), |ui| {
+            let lowest = network
+                .nodes
+                .values()
+                .map(|node| node.head)
+                .fold(f32::INFINITY, f32::min);
+            ui.label(format!(
+                "Segments {} / Junctions {}",
+                network.nodes.len(),
+                network.junctions()
+            ));
+            if lowest.is_finite() {
+                ui.label(format!("Lowest Head: {:.0}%", lowest * 100.0));
+            }
+            ui.separator();
+            valves.for_each_mut(|(entity, mut valve)| {
+                let head = network.head(entity).unwrap_or_default();
+                ui.checkbox(
+                    &mut valve.open,
+                    format!("Valve {} ({:.0}%)", entity.id(), head * 100.0),
+                );
+            });
+        });
+}
+
+pub fn terrain_editor_ui(
+    egui_ctx: ResMut<EguiContext>,
+    mut terrain_editor: ResMut<TerrainEditor>,
+    facility_map: Res<FacilityMap>,
+) {
+    egui::Window::new("Terrain")
+        .default_pos((1000.0, 500.0))
+        .show(egui_ctx.ctx(), |ui| {
+            // only touch the editor on changes, the surface map is rebuilt from it
+            let (mut enabled, mut snap) = (terrain_editor.enabled, terrain_editor.snap);
+            ui.checkbox(&mut enabled, "Edit Terrain");
+            ui.add(egui::Slider::new(&mut snap, 0.0..=48.0).text("Snap"));
+            if enabled != terrain_editor.enabled || snap.round() != terrain_editor.snap {
+                terrain_editor.enabled = enabled;
+                terrain_editor.snap = snap.round();
+            }
+            ui.label(format!(
+                "Shapes {} / Vertices {}",
+                terrain_editor.shapes.len(),
+                terrain_editor
+                    .shapes
+                    .iter()
+                    .map(|shape| shape.vertices.len())
+                    .sum::<usize>()
+            ));
+            if let Some(vertex) = terrain_editor
+                .selected
+                .and_then(|selected| terrain_editor.vertex(selected))
+            {
+                ui.label(format!("Selected ({:.0}, {:.0})", vertex.x, vertex.y));
+            }
+            let shape = terrain_editor
+                .selected
+                .map(|(shape, _)| shape)
+                .or(terrain_editor.active)
+                .and_then(|shape| terrain_editor.shapes.get(shape));
+            if let Some(current) = shape.map(|shape| shape.surface) {
+                let mut surface = current;
+                ui.horizontal_wrapped(|ui| {
+                    ui.radio_value(&mut surface, None, "Wall");
+                    for option in SurfaceType::ALL {
+                        ui.radio_value(&mut surface, Some(option), option.to_string());
+                    }
+                });
+                if surface != current {
+                    terrain_editor.set_surface(surface);
+                }
+            }
+            if terrain_editor.enabled {
+                ui.label("Right click: add vertex, Left drag: move vertex");
+                ui.label("N: new shape, C: close, I: insert, Del: delete");
+            }
+            ui.horizontal(|ui| {
+                if ui
+                    .add(egui::Button::new("Undo").enabled(!terrain_editor.history.is_empty()))
+                    .clicked()
+                {
+                    terrain_editor.undo();
+                }
+                let path = facility_map.terrain_path();
+                if ui.button("Save").clicked() {
+                    terrain_editor.status = match terrain_editor.save(&path) {
+                        Ok(()) => format!("Saved {}", path.display()),
+                        Err(error) => format!("Save failed: {}", error),
+                    };
+                }
+                if ui.button("Load").clicked() {
+                    terrain_editor.status = match terrain_editor.load(&path) {
+                        Ok(()) => format!("Loaded {}", path.display()),
+                        Err(error) => format!("Load failed: {}", error),
+                    };
+                }
+            });
+            if !terrain_editor.status.is_empty() {
+                ui.label(terrain_editor.status.as_str());
+            }
         });
 }
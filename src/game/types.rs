@@ -1,8 +1,10 @@
 use std::fmt::Display;
 
+#[cfg(feature = "dev")]
 use bevy_inspector_egui::Inspectable;
 
-#[derive(serde::Deserialize, Debug, Clone, PartialEq, Inspectable, Copy)]
+#[derive(serde::Deserialize, Debug, Clone, PartialEq, Copy)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub enum ItemType {
     Item,
     Marker(MarkerItemType),
@@ -29,12 +31,26 @@ impl Display for ItemType {
     }
 }
 
-#[derive(serde::Deserialize, Debug, Clone, PartialEq, Inspectable, Copy)]
+#[derive(serde::Deserialize, Debug, Clone, PartialEq, Copy)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub enum RobotItemType {
     None,
-    Camera,
+    Camera {
+        #[serde(default)]
+        width: f32,
+        #[serde(default)]
+        height: f32,
+        #[serde(default)]
+        noise: f32,
+        #[serde(default)]
+        spectrum: Spectrum,
+    },
     CameraLens(CameraLensType),
-    Body,
+    Body {
+        /// In kilograms, unlimited at zero.
+        #[serde(default)]
+        max_payload: f32,
+    },
     GroundPropulsion,
     Connector,
     Battery {
@@ -45,6 +61,26 @@ pub enum RobotItemType {
         #[serde(default)]
         charge_speed: f32,
     },
+    RangeSensor {
+        #[serde(default)]
+        rays: usize,
+        #[serde(default)]
+        field_of_view: f32,
+        #[serde(default)]
+        max_range: f32,
+        #[serde(default)]
+        noise: f32,
+        #[serde(default)]
+        power_consumption: f32,
+    },
+    GasSensor {
+        #[serde(default)]
+        sensitivity: f32,
+        #[serde(default)]
+        response_time: f32,
+        #[serde(default)]
+        power_consumption: f32,
+    },
 }
 
 impl Default for RobotItemType {
@@ -59,15 +95,31 @@ impl Display for RobotItemType {
             Self::None => write!(f, "None"),
             Self::Camera { .. } => write!(f, "Camera"),
             Self::CameraLens { .. } => write!(f, "Camera Lens"),
-            Self::Body => write!(f, "Body"),
+            Self::Body { .. } => write!(f, "Body"),
             Self::GroundPropulsion => write!(f, "Ground Propulsion"),
             Self::Connector => write!(f, "Connector"),
             Self::Battery { .. } => write!(f, "Battery"),
+            Self::RangeSensor { .. } => write!(f, "Range Sensor"),
+            Self::GasSensor { .. } => write!(f, "Gas Sensor"),
         }
     }
 }
 
-#[derive(serde::Deserialize, Debug, Clone, PartialEq, Inspectable, Copy)]
+#[derive(serde::Deserialize, Debug, Clone, PartialEq, Copy)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub enum Spectrum {
+    Visible,
+    Thermal,
+}
+
+impl Default for Spectrum {
+    fn default() -> Self {
+        Self::Visible
+    }
+}
+
+#[derive(serde::Deserialize, Debug, Clone, PartialEq, Copy)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub enum CameraLensType {
     None,
     Wide {
@@ -98,7 +150,8 @@ impl Display for CameraLensType {
     }
 }
 
-#[derive(serde::Deserialize, Debug, Clone, PartialEq, Inspectable, Copy)]
+#[derive(serde::Deserialize, Debug, Clone, PartialEq, Copy)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub enum ManometerItemType {
     None,
     Background,
@@ -108,6 +161,14 @@ pub enum ManometerItemType {
     Icon {
         #[serde(default)]
         progress: f32,
+        #[serde(default)]
+        range: (f32, f32),
+        #[serde(default)]
+        allowed: (f32, f32),
+        #[serde(default)]
+        unit: PressureUnit,
+        #[serde(default)]
+        interval: f32,
     },
 }
 
@@ -130,7 +191,103 @@ impl Display for ManometerItemType {
     }
 }
 
-#[derive(serde::Deserialize, Debug, Clone, PartialEq, Inspectable, Copy)]
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq, Copy)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub enum PressureUnit {
+    Bar,
+    Psi,
+    KiloPascal,
+}
+
+impl Default for PressureUnit {
+    fn default() -> Self {
+        Self::Bar
+    }
+}
+
+impl Display for PressureUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bar => write!(f, "bar"),
+            Self::Psi => write!(f, "psi"),
+            Self::KiloPascal => write!(f, "kPa"),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq, Copy)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub enum SurfaceType {
+    Concrete,
+    Grating,
+    Gravel,
+    Grass,
+    Mud,
+}
+
+pub struct SurfaceProperties {
+    /// Scales the propulsion force.
+    pub friction: f32,
+    /// Scales the linear and angular damping.
+    pub rolling_resistance: f32,
+    /// Scales the power used for driving.
+    pub power_cost: f32,
+}
+
+impl SurfaceType {
+    pub const ALL: [SurfaceType; 5] = [
+        Self::Concrete,
+        Self::Grating,
+        Self::Gravel,
+        Self::Grass,
+        Self::Mud,
+    ];
+
+    pub fn properties(&self) -> SurfaceProperties {
+        let (friction, rolling_resistance, power_cost) = match self {
+            Self::Concrete => (1.0, 1.0, 1.0),
+            Self::Grating => (0.9, 1.1, 1.05),
+            Self::Gravel => (0.8, 1.5, 1.3),
+            Self::Grass => (0.7, 1.8, 1.4),
+            Self::Mud => (0.4, 3.0, 2.2),
+        };
+        SurfaceProperties {
+            friction,
+            rolling_resistance,
+            power_cost,
+        }
+    }
+
+    /// Finds the surface in a layer or tile set name like `Mud_patches`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        Self::ALL
+            .iter()
+            .find(|surface| name.contains(&surface.to_string().to_lowercase()))
+            .copied()
+    }
+}
+
+impl Default for SurfaceType {
+    fn default() -> Self {
+        Self::Concrete
+    }
+}
+
+impl Display for SurfaceType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Concrete => write!(f, "Concrete"),
+            Self::Grating => write!(f, "Grating"),
+            Self::Gravel => write!(f, "Gravel"),
+            Self::Grass => write!(f, "Grass"),
+            Self::Mud => write!(f, "Mud"),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Debug, Clone, PartialEq, Copy)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub enum MarkerItemType {
     None,
     Waypoint,
@@ -151,12 +308,31 @@ impl Display for MarkerItemType {
     }
 }
 
-#[derive(serde::Deserialize, Debug, Clone, PartialEq, Inspectable, Copy)]
+#[derive(serde::Deserialize, Debug, Clone, PartialEq, Copy)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
 pub enum EnvironmentItemType {
     None,
     Ground,
     Wall,
     Pipe,
+    HotSpot {
+        #[serde(default)]
+        max_temperature: f32,
+    },
+    ChargingStation {
+        #[serde(default)]
+        range: f32,
+    },
+    Door {
+        #[serde(default)]
+        reach: f32,
+    },
+    Gate {
+        #[serde(default)]
+        reach: f32,
+        #[serde(default)]
+        access: AccessCondition,
+    },
 }
 
 impl Default for EnvironmentItemType {
@@ -172,6 +348,37 @@ impl Display for EnvironmentItemType {
             Self::Ground => write!(f, "Ground"),
             Self::Wall => write!(f, "Wall"),
             Self::Pipe => write!(f, "Pipe"),
+            Self::HotSpot { .. } => write!(f, "Hot Spot"),
+            Self::ChargingStation { .. } => write!(f, "Charging Station"),
+            Self::Door { .. } => write!(f, "Door"),
+            Self::Gate { .. } => write!(f, "Gate"),
         }
     }
-}
\ No newline at end of file
+}
+
+/// What a robot needs before a gate lets it through.
+#[derive(serde::Deserialize, Debug, Clone, PartialEq, Copy)]
+#[cfg_attr(feature = "dev", derive(Inspectable))]
+pub enum AccessCondition {
+    None,
+    /// Share of compliant inspection targets in the facility.
+    Compliance { fraction: f32 },
+    /// Number of logged manometer and thermal readings.
+    Inspections { count: usize },
+}
+
+impl Default for AccessCondition {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl Display for AccessCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::Compliance { fraction } => write!(f, "{:.0}% compliance", fraction * 100.0),
+            Self::Inspections { count } => write!(f, "{} inspections", count),
+        }
+    }
+}
@@ -0,0 +1,12 @@
+pub mod consts;
+#[cfg(feature = "dev")]
+pub mod dev;
+pub mod game;
+pub mod utils;
+
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub enum GameState {
+    AssetLoading,
+    SpriteLoading,
+    Game,
+}
@@ -1,8 +1,14 @@
 use std::ops::Range;
 
-use bevy::prelude::Entity;
+use bevy::{
+    prelude::{Color, Entity, Transform, Vec2, Window},
+    render::camera::OrthographicProjection,
+};
+use bevy_rapier2d::prelude::RigidBodyPosition;
 use num_traits::Num;
 
+use crate::consts::PHYSICS_SCALE;
+
 use crate::game::components::robot::ParentEntity;
 
 pub fn map_range<T: Num + Copy>(from_range: &Range<T>, to_range: &Range<T>, s: T) -> T {
@@ -10,3 +16,48 @@ pub fn map_range<T: Num + Copy>(from_range: &Range<T>, to_range: &Range<T>, s: T
         + (s - from_range.start) * (to_range.end - to_range.start)
             / (from_range.end - from_range.start)
 }
+
+pub fn world_position(rb_pos: &RigidBodyPosition) -> Vec2 {
+    Vec2::new(rb_pos.position.translation.x, rb_pos.position.translation.y) * PHYSICS_SCALE
+}
+
+/// The cursor in world coordinates, following the camera's pan and zoom.
+pub fn cursor_world_position(
+    window: &Window,
+    camera: &Transform,
+    projection: &OrthographicProjection,
+) -> Option<Vec2> {
+    let window_size = Vec2::new(window.width(), window.height());
+    window.cursor_position().map(|cursor| {
+        let normalized = (cursor / window_size) * 2. - Vec2::ONE;
+        camera.translation.truncate()
+            + normalized * Vec2::new(projection.right, projection.top) * projection.scale
+    })
+}
+
+/// Maps 0.0..=1.0 to an ironbow palette as used by thermal imagers.
+pub fn false_color(t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    Color::rgb(
+        (t * 2.0).min(1.0),
+        (t * 2.0 - 0.8).clamp(0.0, 1.0),
+        if t < 0.35 {
+            0.3 + t * 1.5
+        } else {
+            (t - 0.75).max(0.0) * 4.0
+        },
+    )
+}
+
+/// Seconds as "1h 05m", "4m 12s" or "9s".
+pub fn format_duration(seconds: f64) -> String {
+    let seconds = seconds.max(0.0) as u64;
+    let (hours, minutes) = (seconds / 3600, seconds / 60 % 60);
+    if hours > 0 {
+        format!("{}h {:02}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {:02}s", minutes, seconds % 60)
+    } else {
+        format!("{}s", seconds)
+    }
+}
@@ -0,0 +1,191 @@
+mod common;
+
+use bevy::prelude::*;
+use common::TestApp;
+use idle_inspection::game::{
+    components::robot::*,
+    resources::item_collection::{ItemCollection, LoadedItem},
+    systems::load,
+    types::*,
+};
+
+/// Enough frames for a three level deep robot to attach and for rapier to build the joints.
+const ATTACH_FRAMES: usize = 10;
+
+fn bare_body(app: &mut TestApp) -> Entity {
+    app.spawn(|spawner, items, commands| {
+        spawner
+            .item(&items.simple_body)
+            .seed(0)
+            .transform(Transform::from_xyz(100.0, 50.0, 0.0))
+            .build(commands)
+    })
+}
+
+fn size_and_type(
+    app: &TestApp,
+    handle: fn(&ItemCollection) -> &Handle<LoadedItem>,
+) -> (ItemSize, ItemType) {
+    let world = app.world();
+    let item_collection = world.get_resource::<ItemCollection>().unwrap();
+    let items = world.get_resource::<Assets<LoadedItem>>().unwrap();
+    let item = items.get(handle(item_collection)).unwrap();
+    (item.item_size, item.item_type)
+}
+
+fn find_body(app: &mut TestApp) -> Entity {
+    let mut bodies = app.world_mut().query_filtered::<Entity, With<RobotMass>>();
+    let bodies = bodies.iter(app.world()).collect::<Vec<_>>();
+    assert_eq!(bodies.len(), 1, "expected a single robot");
+    bodies[0]
+}
+
+#[test]
+fn startup_robot_is_assembled() {
+    let mut app = TestApp::boot();
+    app.run_system(load::spawn_entities);
+    app.step(ATTACH_FRAMES);
+    let body = find_body(&mut app);
+
+    assert_eq!(app.parent_tag(body), ParentEntity::Robot(Some(body)));
+    assert_eq!(app.robot_parts(body).len(), 8);
+
+    let slots = [
+        (AttachmentPointId::LineFollowerCamera, JointType::Fixed),
+        (AttachmentPointId::GroundPropulsionLeft, JointType::Fixed),
+        (AttachmentPointId::GroundPropulsionRight, JointType::Fixed),
+        (AttachmentPointId::MainCamera, JointType::Ball),
+        (AttachmentPointId::MainBattery, JointType::Fixed),
+    ];
+    for (aid, joint_type) in slots {
+        let item = app.attached(body, aid);
+        assert_eq!(app.parent_tag(item), ParentEntity::Robot(Some(body)));
+        assert_eq!(app.joint_type(item), joint_type, "joint at {}", aid);
+        assert!(app.has_joint(item), "no joint at {}", aid);
+        app.assert_placed(item, body, aid);
+    }
+
+    let mast = app.attached(body, AttachmentPointId::MainCamera);
+    let camera = app.attached(mast, AttachmentPointId::FirstCamera);
+    let lens = app.attached(camera, AttachmentPointId::CameraLens);
+    assert!(!app
+        .attachment(mast, AttachmentPointId::SecondCamera)
+        .is_attached());
+    assert_eq!(app.joint_type(lens), JointType::Prismatic);
+    for (item, parent, aid) in [
+        (camera, mast, AttachmentPointId::FirstCamera),
+        (lens, camera, AttachmentPointId::CameraLens),
+    ] {
+        assert_eq!(app.parent_tag(item), ParentEntity::Robot(Some(body)));
+        assert!(app.has_joint(item), "no joint at {}", aid);
+        app.assert_placed(item, parent, aid);
+    }
+}
+
+#[test]
+fn attachment_checks_type_and_size() {
+    let mut app = TestApp::boot();
+    let body = bare_body(&mut app);
+    app.step(1);
+
+    let (track_size, track_type) = size_and_type(&app, |items| &items.simple_track);
+    let (battery_size, battery_type) = size_and_type(&app, |items| &items.simple_battery);
+    let (zoom_size, zoom_type) = size_and_type(&app, |items| &items.camera_zoom);
+
+    let propulsion = app.attachment(body, AttachmentPointId::GroundPropulsionLeft);
+    assert!(propulsion.is_compatible(&track_size, &track_type));
+    assert!(!propulsion.is_compatible(&battery_size, &battery_type));
+    let line_follower = app.attachment(body, AttachmentPointId::LineFollowerCamera);
+    assert!(!line_follower.is_compatible(&zoom_size, &zoom_type));
+    let main_camera = app.attachment(body, AttachmentPointId::MainCamera);
+    assert!(main_camera.is_compatible(&zoom_size, &zoom_type));
+}
+
+#[test]
+fn incompatible_attachments_are_not_attached() {
+    let mut app = TestApp::boot();
+    let body = bare_body(&mut app);
+    app.step(1);
+    let battery = app.spawn(|spawner, items, commands| {
+        spawner
+            .attachment(
+                &items.simple_battery,
+                AttachmentPointId::GroundPropulsionLeft,
+                body,
+            )
+            .build(commands)
+    });
+    let zoom = app.spawn(|spawner, items, commands| {
+        spawner
+            .attachment(
+                &items.camera_zoom,
+                AttachmentPointId::LineFollowerCamera,
+                body,
+            )
+            .build(commands)
+    });
+    app.step(ATTACH_FRAMES);
+
+    for (item, aid) in [
+        (battery, AttachmentPointId::GroundPropulsionLeft),
+        (zoom, AttachmentPointId::LineFollowerCamera),
+    ] {
+        assert_eq!(app.parent_tag(item), ParentEntity::WaitForAttach);
+        assert!(app.world().get::<WantToAttach>(item).is_some());
+        assert!(!app.has_joint(item));
+        assert!(!app.attachment(body, aid).is_attached(), "{} attached", aid);
+    }
+    assert_eq!(app.robot_parts(body), vec![body]);
+}
+
+#[test]
+fn replacing_an_item_swaps_the_slot() {
+    let mut app = TestApp::boot();
+    app.run_system(load::spawn_entities);
+    app.step(ATTACH_FRAMES);
+    let body = find_body(&mut app);
+    let aid = AttachmentPointId::LineFollowerCamera;
+    let old_camera = app.attached(body, aid);
+
+    app.detach(body, aid);
+    let thermal = app.spawn(|spawner, items, commands| {
+        spawner
+            .attachment(&items.thermal_camera, aid, body)
+            .build(commands)
+    });
+    app.step(ATTACH_FRAMES);
+
+    assert!(!app.exists(old_camera));
+    assert_eq!(app.attached(body, aid), thermal);
+    assert_eq!(app.parent_tag(thermal), ParentEntity::Robot(Some(body)));
+    assert!(app.has_joint(thermal));
+    app.assert_placed(thermal, body, aid);
+    assert_eq!(app.robot_parts(body).len(), 8);
+}
+
+#[test]
+fn detaching_removes_the_whole_branch() {
+    let mut app = TestApp::boot();
+    app.run_system(load::spawn_entities);
+    app.step(ATTACH_FRAMES);
+    let body = find_body(&mut app);
+    let mast = app.attached(body, AttachmentPointId::MainCamera);
+    let camera = app.attached(mast, AttachmentPointId::FirstCamera);
+    let lens = app.attached(camera, AttachmentPointId::CameraLens);
+
+    app.detach(body, AttachmentPointId::MainCamera);
+    app.step(ATTACH_FRAMES);
+
+    assert!(!app
+        .attachment(body, AttachmentPointId::MainCamera)
+        .is_attached());
+    for item in [mast, camera, lens] {
+        assert!(!app.exists(item));
+    }
+    assert_eq!(app.robot_parts(body).len(), 5);
+    app.assert_placed(
+        app.attached(body, AttachmentPointId::MainBattery),
+        body,
+        AttachmentPointId::MainBattery,
+    );
+}
@@ -0,0 +1,234 @@
+#![allow(dead_code)]
+
+use std::time::{Duration, Instant};
+
+use bevy::{app::AppBuilder, ecs::system::CommandQueue, prelude::*};
+use bevy_rapier2d::{na::Isometry2, physics::JointHandleComponent, prelude::*};
+use idle_inspection::{
+    consts::PHYSICS_SCALE,
+    game::{
+        builders::item::ItemSpawner,
+        components::robot::*,
+        plugins::inspection::TIMESTEP_1_PER_SECOND,
+        resources::{
+            facility_map::FacilityMap,
+            item_collection::{ItemCollection, LoadedItem},
+            item_information::InformationCollection,
+            scenario::{Scenario, SimRun},
+            world_seed::WorldSeed,
+        },
+        simulation::{HeadlessPlugin, SimulationPlugin},
+    },
+    GameState,
+};
+
+pub const TIMESTEP: f32 = 1.0 / 60.0;
+const LOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A headless app with the item assets loaded, on an empty map with a fixed seed and clock.
+pub struct TestApp {
+    pub app: AppBuilder,
+}
+
+impl TestApp {
+    pub fn boot() -> Self {
+        Self::boot_with(|_| {})
+    }
+
+    /// Boots with more plugins on top of the simulation, the way the game adds them.
+    pub fn boot_with(plugins: impl FnOnce(&mut AppBuilder)) -> Self {
+        let mut app = App::build();
+        app.insert_resource(WorldSeed::new(0))
+            .insert_resource(FacilityMap::empty())
+            .insert_resource(Scenario::default())
+            .insert_resource(SimRun::new(TIMESTEP, f32::INFINITY))
+            .add_plugins(MinimalPlugins)
+            .add_plugin(HeadlessPlugin)
+            .add_plugin(SimulationPlugin);
+        plugins(&mut app);
+        let mut test_app = Self { app };
+
+        let started = Instant::now();
+        while test_app.state() != &GameState::Game {
+            assert!(
+                started.elapsed() < LOAD_TIMEOUT,
+                "item assets did not load, stuck in {:?}",
+                test_app.state()
+            );
+            test_app.app.app.update();
+            std::thread::yield_now();
+        }
+        // the fixed step systems must not have run before the game started
+        let frames = test_app.world().get_resource::<SimRun>().unwrap().frames;
+        assert!(
+            frames as f64 * (TIMESTEP as f64) < TIMESTEP_1_PER_SECOND,
+            "the clock ran for {} frames while loading",
+            frames
+        );
+        test_app
+    }
+
+    pub fn state(&self) -> &GameState {
+        self.world()
+            .get_resource::<State<GameState>>()
+            .unwrap()
+            .current()
+    }
+
+    pub fn world(&self) -> &World {
+        &self.app.app.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.app.app.world
+    }
+
+    pub fn step(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.app.app.update();
+        }
+    }
+
+    /// Runs a system once, outside of the schedule.
+    pub fn run_system<Params, S: System<In = (), Out = ()>>(
+        &mut self,
+        system: impl IntoSystem<Params, S>,
+    ) {
+        let mut system = system.system();
+        let world = self.world_mut();
+        system.initialize(world);
+        system.run((), world);
+        system.apply_buffers(world);
+    }
+
+    /// Spawns a blueprint through the `ItemSpawner`, like the game does.
+    pub fn spawn(
+        &mut self,
+        blueprint: impl FnOnce(&ItemSpawner, &ItemCollection, &mut Commands) -> Entity,
+    ) -> Entity {
+        let mut queue = CommandQueue::default();
+        let entity = {
+            let world = self.world();
+            let items = world.get_resource::<Assets<LoadedItem>>().unwrap();
+            let information_collection = world.get_resource::<InformationCollection>().unwrap();
+            let item_collection = world.get_resource::<ItemCollection>().unwrap();
+            let spawner = ItemSpawner::new(items, information_collection, item_collection, 0);
+            let mut commands = Commands::new(&mut queue, world);
+            blueprint(&spawner, item_collection, &mut commands)
+        };
+        queue.apply(self.world_mut());
+        entity
+    }
+
+    /// Detaches whatever sits in the slot, the way the attachment menu does.
+    pub fn detach(&mut self, parent: Entity, aid: AttachmentPointId) {
+        let mut queue = CommandQueue::default();
+        {
+            let world = &mut self.app.app.world;
+            let mut attachment = world
+                .get::<AttachmentMap<Attachment>>(parent)
+                .and_then(|attachments| attachments.0.get(&aid).cloned())
+                .expect("parent has the attachment point");
+            attachment.detach(&mut Commands::new(&mut queue, world));
+            world
+                .get_mut::<AttachmentMap<Attachment>>(parent)
+                .unwrap()
+                .0
+                .insert(aid, attachment);
+        }
+        queue.apply(self.world_mut());
+    }
+
+    pub fn exists(&self, entity: Entity) -> bool {
+        self.world().get_entity(entity).is_some()
+    }
+
+    pub fn parent_tag(&self, entity: Entity) -> ParentEntity {
+        *self.world().get::<ParentEntity>(entity).unwrap()
+    }
+
+    pub fn attachment(&self, parent: Entity, aid: AttachmentPointId) -> Attachment {
+        self.world()
+            .get::<AttachmentMap<Attachment>>(parent)
+            .and_then(|attachments| attachments.0.get(&aid).cloned())
+            .expect("parent has the attachment point")
+    }
+
+    /// The item attached to the slot, panics if it is empty.
+    pub fn attached(&self, parent: Entity, aid: AttachmentPointId) -> Entity {
+        self.attachment(parent, aid)
+            .attached
+            .map(|(item, _)| item)
+            .unwrap_or_else(|| panic!("nothing attached at {}", aid))
+    }
+
+    pub fn joint_type(&self, entity: Entity) -> JointType {
+        *self.world().get::<JointType>(entity).unwrap()
+    }
+
+    pub fn has_joint(&self, entity: Entity) -> bool {
+        self.world().get::<JointHandleComponent>(entity).is_some()
+    }
+
+    /// All items tagged as part of the robot, including the body.
+    pub fn robot_parts(&mut self, body: Entity) -> Vec<Entity> {
+        let mut query = self
+            .world_mut()
+            .query::<(Entity, &ParentEntity, &ItemName)>();
+        query
+            .iter(self.world())
+            .filter(|(_, tag, _)| **tag == ParentEntity::Robot(Some(body)))
+            .map(|(entity, ..)| entity)
+            .collect()
+    }
+
+    /// Position in pixels and rotation in radians of an item in the frame of its parent.
+    pub fn local_position(&self, child: Entity, parent: Entity) -> (Vec2, f32) {
+        let position = |entity: Entity| {
+            self.world()
+                .get::<RigidBodyPosition>(entity)
+                .unwrap()
+                .position
+        };
+        let local: Isometry2<f32> = position(parent).inverse() * position(child);
+        (
+            Vec2::new(local.translation.x, local.translation.y) * PHYSICS_SCALE,
+            local.rotation.angle(),
+        )
+    }
+
+    /// Where `spawn_joints` places an item on the attachment point of its parent.
+    pub fn expected_local_position(
+        &self,
+        child: Entity,
+        parent: Entity,
+        aid: AttachmentPointId,
+    ) -> (Vec2, f32) {
+        let attachment = self.attachment(parent, aid);
+        let origin = self.world().get::<ItemOrigin>(child).unwrap().to_vec2();
+        let angle = attachment.transform.rotation.to_axis_angle().1;
+        let offset = Quat::from_rotation_z(angle)
+            .mul_vec3(origin.extend(0.0))
+            .truncate();
+        (attachment.transform.translation.truncate() - offset, angle)
+    }
+
+    pub fn assert_placed(&self, child: Entity, parent: Entity, aid: AttachmentPointId) {
+        let (position, angle) = self.local_position(child, parent);
+        let (expected_position, expected_angle) = self.expected_local_position(child, parent, aid);
+        assert!(
+            position.distance(expected_position) < 0.5,
+            "{} at {} instead of {}",
+            aid,
+            position,
+            expected_position
+        );
+        assert!(
+            (angle - expected_angle).abs() < 0.01,
+            "{} rotated by {} instead of {}",
+            aid,
+            angle,
+            expected_angle
+        );
+    }
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use common::TestApp;
+use idle_inspection::game::{
+    components::robot::*,
+    plugins::save::SavePlugin,
+    resources::save_game::{SaveGames, SaveRequest, SaveSlot},
+};
+
+/// Enough frames for a robot to attach and for rapier to build the joints.
+const ATTACH_FRAMES: usize = 10;
+
+/// Boots with the `SavePlugin` on a save directory of its own.
+fn boot_with_saves(name: &str) -> (TestApp, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("idle_inspection_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let saves = dir.clone();
+    let app = TestApp::boot_with(move |app| {
+        let mut save_games = SaveGames::default();
+        save_games.dir = saves;
+        save_games.refresh();
+        app.insert_resource(save_games).add_plugin(SavePlugin);
+    });
+    (app, dir)
+}
+
+fn request(app: &mut TestApp, request: SaveRequest) {
+    app.world_mut()
+        .get_resource_mut::<SaveGames>()
+        .unwrap()
+        .request = Some(request);
+}
+
+fn bodies(app: &mut TestApp) -> Vec<Entity> {
+    let mut bodies = app.world_mut().query_filtered::<Entity, With<RobotMass>>();
+    bodies.iter(app.world()).collect()
+}
+
+#[test]
+fn boots_with_save_plugin() {
+    let (mut app, dir) = boot_with_saves("boot");
+    app.step(ATTACH_FRAMES);
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn load_replaces_the_robot() {
+    let (mut app, dir) = boot_with_saves("load");
+    app.spawn(|spawner, items, commands| {
+        spawner
+            .item(&items.simple_body)
+            .transform(Transform::from_xyz(100.0, 50.0, 0.0))
+            .build(commands)
+    });
+    app.step(ATTACH_FRAMES);
+    let saved = bodies(&mut app);
+    assert_eq!(saved.len(), 1);
+
+    request(&mut app, SaveRequest::Save(SaveSlot::Manual(0)));
+    app.step(1);
+    let path = app
+        .world()
+        .get_resource::<SaveGames>()
+        .unwrap()
+        .path(SaveSlot::Manual(0));
+    assert!(path.exists(), "nothing saved at {}", path.display());
+
+    request(&mut app, SaveRequest::Load(SaveSlot::Manual(0)));
+    app.step(ATTACH_FRAMES);
+    let loaded = bodies(&mut app);
+    assert_eq!(loaded.len(), 1);
+    assert_ne!(loaded[0], saved[0], "the saved robot was not replaced");
+    assert_eq!(
+        app.parent_tag(loaded[0]),
+        ParentEntity::Robot(Some(loaded[0]))
+    );
+    let _ = fs::remove_dir_all(dir);
+}